@@ -5,9 +5,14 @@ use std::io::{Read, Write};
 use tauri::Manager;
 
 use pandoc::{
-    clear_sessions, convert_markdown, install_crossref, install_pandoc, is_crossref_installed,
-    is_pandoc_installed, list_templates, pandoc_version, prepare_input_payload,
-    prepare_template_protected,
+    clear_sessions, clear_template_previews, convert_markdown, delete_user_template,
+    generate_reference_doc, generate_template_preview, import_user_template, inspect_template,
+    inspect_template_file, install_crossref, install_license, install_pandoc, install_template_pack,
+    is_crossref_installed, is_pandoc_installed, license_status, list_template_packs, list_templates,
+    list_user_templates, pandoc_version, prepare_input_payload, prepare_template_from_spec,
+    prepare_template_protected, protect_template_file, remove_license, rename_user_template,
+    uninstall_template_pack, validate_custom_styles, validate_template_pack,
+    validate_template_styles,
 };
 
 #[tauri::command]
@@ -61,6 +66,11 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
+        .setup(|app| {
+            // 清理上次运行残留的 runtime 模板（其中可能有解密后的受保护模板）
+            pandoc::runtime::sweep_runtime(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             install_pandoc,
             install_crossref,
@@ -70,7 +80,27 @@ pub fn run() {
             convert_markdown,
             prepare_input_payload,
             prepare_template_protected,
+            prepare_template_from_spec,
+            generate_reference_doc,
+            inspect_template,
+            inspect_template_file,
+            protect_template_file,
             list_templates,
+            import_user_template,
+            rename_user_template,
+            delete_user_template,
+            list_user_templates,
+            validate_template_pack,
+            install_template_pack,
+            uninstall_template_pack,
+            list_template_packs,
+            install_license,
+            license_status,
+            remove_license,
+            generate_template_preview,
+            clear_template_previews,
+            validate_custom_styles,
+            validate_template_styles,
             clear_sessions,
             export_logs
         ])
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+/// Pandoc 风格的属性块：`{#id .class key="value"}`
+#[derive(Debug, Clone, Default)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub values: HashMap<String, String>,
+}
+
+impl Attributes {
+    /// 解析属性文本，外层花括号可有可无
+    pub fn parse(input: &str) -> Self {
+        let trimmed = input.trim();
+        let inner = trimmed
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .unwrap_or(trimmed);
+
+        let mut attrs = Attributes::default();
+        for token in tokenize(inner) {
+            if let Some(id) = token.strip_prefix('#') {
+                if !id.is_empty() {
+                    attrs.id = Some(id.to_string());
+                }
+            } else if let Some(class) = token.strip_prefix('.') {
+                if !class.is_empty() {
+                    attrs.classes.push(class.to_string());
+                }
+            } else if let Some((key, value)) = token.split_once('=') {
+                attrs
+                    .values
+                    .insert(key.trim().to_string(), unquote(value.trim()).to_string());
+            } else if !token.is_empty() {
+                // 裸单词按 class 处理，例如 ```csv
+                attrs.classes.push(token.to_string());
+            }
+        }
+        attrs
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c.eq_ignore_ascii_case(class))
+    }
+
+    /// 写回 `{#id .class key="value"}` 形式，键按字母顺序输出
+    pub fn render(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(id) = &self.id {
+            parts.push(format!("#{}", id));
+        }
+        parts.extend(self.classes.iter().map(|c| format!(".{}", c)));
+
+        let mut keys: Vec<&String> = self.values.keys().collect();
+        keys.sort();
+        for key in keys {
+            // 与 parse 对称：值原样写出，含双引号时改用单引号
+            let value = &self.values[key];
+            let quote = if value.contains('"') { '\'' } else { '"' };
+            parts.push(format!("{}={}{}{}", key, quote, value, quote));
+        }
+        format!("{{{}}}", parts.join(" "))
+    }
+
+    /// 读取布尔属性，支持 true/false/yes/no/on/off/1/0；无法识别的值视为未设置
+    pub fn flag(&self, key: &str) -> Option<bool> {
+        match self.get(key)?.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// 按空白切分，但保留引号内的空白
+pub(crate) fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+
+    for ch in input.chars() {
+        match quote {
+            Some(q) => {
+                current.push(ch);
+                if ch == q {
+                    quote = None;
+                }
+            }
+            None if ch == '"' || ch == '\'' => {
+                current.push(ch);
+                quote = Some(ch);
+            }
+            None if ch.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            None => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+pub(crate) fn unquote(value: &str) -> &str {
+    for q in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    value
+}
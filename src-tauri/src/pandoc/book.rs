@@ -0,0 +1,122 @@
+use regex::Regex;
+use serde_yaml::Value as YamlValue;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 书籍清单文件名：mdBook/GitBook 的 SUMMARY.md 与 Jupyter Book 的 _toc.yml
+const MANIFEST_NAMES: [&str; 3] = ["SUMMARY.md", "_toc.yml", "_toc.yaml"];
+
+pub fn is_manifest_name(name: &str) -> bool {
+    MANIFEST_NAMES.iter().any(|m| m.eq_ignore_ascii_case(name))
+}
+
+/// 在目录中查找清单文件，只看根目录和下两层（压缩包常带一层顶级目录，mdBook 放在 src/）
+pub fn find_manifest(root: &Path) -> Option<PathBuf> {
+    let mut level = vec![root.to_path_buf()];
+    for _ in 0..3 {
+        let mut next = Vec::new();
+        for dir in &level {
+            for name in MANIFEST_NAMES {
+                let candidate = dir.join(name);
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+            if let Ok(entries) = fs::read_dir(dir) {
+                let mut dirs: Vec<PathBuf> = entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_dir())
+                    .collect();
+                dirs.sort();
+                next.extend(dirs);
+            }
+        }
+        level = next;
+    }
+    None
+}
+
+/// 读取清单，按顺序返回章节文件的绝对路径（跳过不存在的条目）
+pub fn read_manifest(manifest: &Path) -> Result<Vec<PathBuf>, String> {
+    let content =
+        fs::read_to_string(manifest).map_err(|e| format!("Failed to read book manifest: {}", e))?;
+    let base = manifest.parent().unwrap_or(Path::new("."));
+    let name = manifest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let entries = if name.ends_with(".md") {
+        parse_summary(&content)?
+    } else {
+        parse_toc_yaml(&content)?
+    };
+
+    let mut chapters: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+        match resolve_chapter(base, &entry) {
+            Some(path) if !chapters.contains(&path) => chapters.push(path),
+            Some(_) => {}
+            None => log::warn!("Chapter '{}' listed in manifest not found", entry),
+        }
+    }
+
+    if chapters.is_empty() {
+        return Err("No chapters found in book manifest".to_string());
+    }
+    Ok(chapters)
+}
+
+/// SUMMARY.md：按出现顺序收集指向本地 Markdown 的链接
+fn parse_summary(content: &str) -> Result<Vec<String>, String> {
+    let link_regex = Regex::new(r"\[[^\]]*\]\((?P<path>[^)\s]*)\)")
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    Ok(link_regex
+        .captures_iter(content)
+        .filter_map(|caps| caps.name("path").map(|m| m.as_str().to_string()))
+        .filter(|path| !path.is_empty() && !path.contains("://"))
+        .map(|path| path.split('#').next().unwrap_or("").to_string())
+        .filter(|path| !path.is_empty())
+        .collect())
+}
+
+/// _toc.yml：依次展开 root、parts、chapters 与嵌套的 sections
+fn parse_toc_yaml(content: &str) -> Result<Vec<String>, String> {
+    let toc: YamlValue =
+        serde_yaml::from_str(content).map_err(|e| format!("Failed to parse _toc.yml: {}", e))?;
+
+    let mut files = Vec::new();
+    if let Some(root) = toc.get("root").and_then(|v| v.as_str()) {
+        files.push(root.to_string());
+    }
+    collect_toc_entries(&toc, &mut files);
+    Ok(files)
+}
+
+fn collect_toc_entries(node: &YamlValue, files: &mut Vec<String>) {
+    for key in ["parts", "chapters", "sections"] {
+        if let Some(items) = node.get(key).and_then(|v| v.as_sequence()) {
+            for item in items {
+                if let Some(file) = item.get("file").and_then(|v| v.as_str()) {
+                    files.push(file.to_string());
+                }
+                collect_toc_entries(item, files);
+            }
+        }
+    }
+}
+
+/// 清单条目可以省略扩展名（_toc.yml 的惯例，也可指向 notebook）
+fn resolve_chapter(base: &Path, entry: &str) -> Option<PathBuf> {
+    let direct = base.join(entry);
+    if direct.is_file() {
+        return Some(direct);
+    }
+    ["md", "markdown", "ipynb"]
+        .iter()
+        .map(|ext| base.join(format!("{}.{}", entry, ext)))
+        .find(|p| p.is_file())
+}
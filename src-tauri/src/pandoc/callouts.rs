@@ -0,0 +1,322 @@
+use regex::Regex;
+use std::collections::HashMap;
+
+use super::attrs::Attributes;
+use super::frontmatter::front_matter_len;
+use super::styles::{DocxStyle, StyleKind};
+use super::tables::{find_fence_close, parse_fence_open};
+
+/// 提示块类型，对应 GitHub alert 的五种类型，其他写法归并到最接近的一种
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CalloutKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl CalloutKind {
+    const ALL: [CalloutKind; 5] = [
+        CalloutKind::Note,
+        CalloutKind::Tip,
+        CalloutKind::Important,
+        CalloutKind::Warning,
+        CalloutKind::Caution,
+    ];
+
+    /// fenced div 的类名：`abstract`、`summary` 等是论文与文档中的普通区块，不当作提示块
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "note" | "info" => Some(Self::Note),
+            "tip" | "hint" | "success" => Some(Self::Tip),
+            "important" => Some(Self::Important),
+            "warning" | "attention" => Some(Self::Warning),
+            "caution" | "danger" | "error" | "failure" | "bug" => Some(Self::Caution),
+            _ => None,
+        }
+    }
+
+    /// `> [!…]` alert 的类型，另外接受 Obsidian callout 的写法
+    fn parse_alert(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "details" | "abstract" | "summary" | "todo" => Some(Self::Note),
+            other => Self::parse(other),
+        }
+    }
+
+    /// 模板中对应的段落样式名，例如 "Note Box"
+    fn style_name(self) -> &'static str {
+        match self {
+            Self::Note => "Note Box",
+            Self::Tip => "Tip Box",
+            Self::Important => "Important Box",
+            Self::Warning => "Warning Box",
+            Self::Caution => "Caution Box",
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Note => "ℹ",
+            Self::Tip => "💡",
+            Self::Important => "❗",
+            Self::Warning => "⚠",
+            Self::Caution => "⛔",
+        }
+    }
+
+    /// 没有自定义标题时使用的默认标题
+    fn label(self) -> &'static str {
+        match self {
+            Self::Note => "说明",
+            Self::Tip => "提示",
+            Self::Important => "重要",
+            Self::Warning => "警告",
+            Self::Caution => "注意",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CalloutStyle {
+    body: String,
+    title: Option<String>,
+}
+
+/// 参考文档中可用的提示块样式
+///
+/// 正文样式名为 `<类型> Box`（如 "Note Box"），标题可另设 `<类型> Box Title`，
+/// 没有标题样式时标题以粗体写在正文样式中。
+#[derive(Debug, Clone, Default)]
+pub struct CalloutStyles {
+    styles: HashMap<CalloutKind, CalloutStyle>,
+}
+
+impl CalloutStyles {
+    pub fn from_styles(styles: &[DocxStyle]) -> Self {
+        // Word 样式名不区分大小写，写出时使用模板中的原名
+        let find = |name: &str| {
+            styles
+                .iter()
+                .find(|s| s.kind == StyleKind::Paragraph && s.name.eq_ignore_ascii_case(name))
+                .map(|s| s.name.clone())
+        };
+
+        let styles = CalloutKind::ALL
+            .iter()
+            .filter_map(|&kind| {
+                let body = find(kind.style_name())?;
+                let title = find(&format!("{} Title", kind.style_name()));
+                Some((kind, CalloutStyle { body, title }))
+            })
+            .collect();
+        Self { styles }
+    }
+}
+
+/// 把 GitHub alert（`> [!NOTE]`）与提示块容器（`::: tip`、`::: {.warning title="…"}`）
+/// 渲染为 Word 提示框
+///
+/// 参考文档定义了对应样式时写成 `custom-style` div，否则退回为带粗体标题的引用块。
+pub fn render_callouts(content: &str, styles: &CalloutStyles) -> Result<String, String> {
+    if !content.contains("[!") && !content.contains(":::") {
+        return Ok(content.to_string());
+    }
+
+    let syntax = CalloutSyntax::new()?;
+    let (front_matter, body) = content.split_at(front_matter_len(content));
+    let mut result = front_matter.to_string();
+    result.push_str(&syntax.render(body, styles));
+    if body.ends_with('\n') && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+struct CalloutSyntax {
+    alert: Regex,
+    div_open: Regex,
+    div_close: Regex,
+}
+
+impl CalloutSyntax {
+    fn new() -> Result<Self, String> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e))
+        };
+        Ok(Self {
+            // Obsidian 写法允许在类型后写标题，`-`/`+` 为折叠标记
+            alert: compile(
+                r"^ {0,3}>[ \t]?\[!(?P<kind>[A-Za-z]+)\][-+]?(?:[ \t]+(?P<title>.*?))?[ \t]*$",
+            )?,
+            div_open: compile(
+                r"^ {0,3}:{3,}[ \t]*(?:\{(?P<attrs>[^}]*)\}|(?P<kind>[A-Za-z]+)(?:[ \t]+(?P<title>.*?))?)[ \t]*$",
+            )?,
+            div_close: compile(r"^ {0,3}:{3,}[ \t]*$")?,
+        })
+    }
+
+    fn render(&self, text: &str, styles: &CalloutStyles) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut output: Vec<String> = Vec::with_capacity(lines.len());
+        let mut i = 0;
+
+        while i < lines.len() {
+            if let Some((fence_char, fence_len, _)) = parse_fence_open(lines[i]) {
+                if let Some(close) = find_fence_close(&lines, i + 1, fence_char, fence_len) {
+                    output.extend(lines[i..=close].iter().map(|l| l.to_string()));
+                    i = close + 1;
+                    continue;
+                }
+            }
+
+            let block = self
+                .alert_block(&lines, i)
+                .or_else(|| self.div_block(&lines, i));
+            let Some((kind, title, body, end)) = block else {
+                output.push(lines[i].to_string());
+                i += 1;
+                continue;
+            };
+
+            if output.last().is_some_and(|l| !l.trim().is_empty()) {
+                output.push(String::new());
+            }
+            let inner = self.render(&body.join("\n"), styles);
+            output.push(callout(kind, title.as_deref(), &inner, styles));
+            i = end;
+            if lines.get(i).is_some_and(|l| !l.trim().is_empty()) {
+                output.push(String::new());
+            }
+        }
+        output.join("\n")
+    }
+
+    /// `> [!NOTE]` 开头、连续 `>` 行组成的 alert，返回 (类型, 标题, 正文行, 结束位置)
+    fn alert_block<'a>(
+        &self,
+        lines: &[&'a str],
+        start: usize,
+    ) -> Option<(CalloutKind, Option<String>, Vec<&'a str>, usize)> {
+        let caps = self.alert.captures(lines[start])?;
+        let kind = CalloutKind::parse_alert(&caps["kind"])?;
+        let title = caps
+            .name("title")
+            .map(|m| m.as_str().to_string())
+            .filter(|t| !t.is_empty());
+
+        let mut end = start + 1;
+        let mut body = Vec::new();
+        while let Some(line) = lines.get(end).and_then(|l| strip_quote_marker(l)) {
+            body.push(line);
+            end += 1;
+        }
+        Some((kind, title, body, end))
+    }
+
+    /// `::: kind [标题]` 或 `::: {.kind title="…"}` 开头的 fenced div
+    fn div_block<'a>(
+        &self,
+        lines: &[&'a str],
+        start: usize,
+    ) -> Option<(CalloutKind, Option<String>, Vec<&'a str>, usize)> {
+        let caps = self.div_open.captures(lines[start])?;
+        let (kind, title) = match caps.name("attrs") {
+            Some(attrs) => {
+                let attrs = Attributes::parse(attrs.as_str());
+                // 已指定样式的 div 交给 pandoc 处理
+                if attrs.get("custom-style").is_some() {
+                    return None;
+                }
+                let kind = attrs.classes.iter().find_map(|c| CalloutKind::parse(c))?;
+                let title = attrs
+                    .get("title")
+                    .map(|t| t.replace("\\\"", "\"").replace("\\\\", "\\"));
+                (kind, title)
+            }
+            None => (
+                CalloutKind::parse(&caps["kind"])?,
+                caps.name("title").map(|m| m.as_str().to_string()),
+            ),
+        };
+
+        let close = self.find_div_close(lines, start + 1)?;
+        let title = title.filter(|t| !t.trim().is_empty());
+        Some((kind, title, lines[start + 1..close].to_vec(), close + 1))
+    }
+
+    fn find_div_close(&self, lines: &[&str], start: usize) -> Option<usize> {
+        let mut depth = 1;
+        let mut idx = start;
+        while idx < lines.len() {
+            if let Some((fence_char, fence_len, _)) = parse_fence_open(lines[idx]) {
+                if let Some(close) = find_fence_close(lines, idx + 1, fence_char, fence_len) {
+                    idx = close + 1;
+                    continue;
+                }
+            }
+            if self.div_close.is_match(lines[idx]) {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            } else if lines[idx].trim_start().starts_with(":::") {
+                depth += 1;
+            }
+            idx += 1;
+        }
+        None
+    }
+}
+
+fn callout(kind: CalloutKind, title: Option<&str>, body: &str, styles: &CalloutStyles) -> String {
+    let heading = format!("{} {}", kind.icon(), title.unwrap_or(kind.label()).trim());
+    let body = body.trim_matches('\n');
+
+    match styles.styles.get(&kind) {
+        Some(style) => {
+            let mut block = format!("::: {{custom-style=\"{}\"}}\n", style.body);
+            match &style.title {
+                Some(title_style) => block.push_str(&format!(
+                    "::: {{custom-style=\"{}\"}}\n{}\n:::\n",
+                    title_style, heading
+                )),
+                None => block.push_str(&format!("**{}**\n", heading)),
+            }
+            if !body.is_empty() {
+                block.push_str(&format!("\n{}\n", body));
+            }
+            block.push_str(":::");
+            block
+        }
+        None => {
+            let mut block = format!("> **{}**", heading);
+            if !body.is_empty() {
+                block.push_str("\n>");
+                for line in body.lines() {
+                    if line.trim().is_empty() {
+                        block.push_str("\n>");
+                    } else {
+                        block.push_str(&format!("\n> {}", line));
+                    }
+                }
+            }
+            block
+        }
+    }
+}
+
+/// 去掉引用标记 `>` 及其后的一个空格，不是引用行时返回 None
+fn strip_quote_marker(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = line[indent..].strip_prefix('>')?;
+    Some(
+        rest.strip_prefix(' ')
+            .or_else(|| rest.strip_prefix('\t'))
+            .unwrap_or(rest),
+    )
+}
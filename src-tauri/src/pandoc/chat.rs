@@ -0,0 +1,219 @@
+use regex::{Captures, Regex};
+
+use super::cjk::{map_outside_code, map_prose};
+
+/// 各家对话工具复制时带出的引用角标
+const CITATION_PATTERNS: [&str; 5] = [
+    // ChatGPT 联网搜索：【4†source】、【3:1†网页】
+    r"【\d+(?:[:：]\d+)*†[^】]*】",
+    // ChatGPT 网页版复制：:contentReference[oaicite:0]{index=0}
+    r":?contentReference\[oaicite:\d+\](?:\{index=\d+\})?",
+    // DeepSeek：[citation:3]、[citation:1,2]
+    r"\[citation:\s*\d+(?:\s*,\s*\d+)*\]",
+    // Kimi：[^1^]
+    r"\[\^\d+\^\]",
+    // Perplexity 等：[[1]](https://...)
+    r"\[\[\d+\]\]\([^)\s]*\)",
+];
+
+/// 规范化从 ChatGPT、DeepSeek、Kimi 等对话工具复制的 Markdown
+///
+/// - `\( \)`、`\[ \]` 数学定界符改为 pandoc 识别的 `$ $`、`$$ $$`
+/// - 删除联网搜索带出的引用角标
+/// - 修正列表前缺少空行、子列表缩进不足、`•` 项目符号等问题
+///
+/// 代码块与行内代码保持原样；加粗紧贴中文标点的问题由 `typeset_cjk` 的强调修正处理。
+pub fn normalize_chat_markdown(content: &str) -> Result<String, String> {
+    let normalizer = ChatNormalizer::new()?;
+    Ok(map_prose(content, |text| {
+        normalizer.normalize_prose(text).join("\n")
+    }))
+}
+
+struct ChatNormalizer {
+    citations: Vec<Regex>,
+    inline_math: Regex,
+    display_math: Regex,
+    list_item: Regex,
+}
+
+impl ChatNormalizer {
+    fn new() -> Result<Self, String> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e))
+        };
+        Ok(Self {
+            citations: CITATION_PATTERNS
+                .iter()
+                .map(|p| compile(p))
+                .collect::<Result<_, _>>()?,
+            inline_math: compile(r"\\\((.+?)\\\)")?,
+            display_math: compile(r"(?s)\\\[(.+?)\\\]")?,
+            list_item: compile(r"^( *)([-*+•●▪]|\d{1,9}[.)])( +)(\S.*)$")?,
+        })
+    }
+
+    /// 处理一段不含围栏代码块的正文，返回处理后的行
+    fn normalize_prose(&self, text: &str) -> Vec<String> {
+        if text.is_empty() {
+            return Vec::new();
+        }
+        // 数学块可能跨行，先在整段上替换
+        let text = map_outside_code(text, |segment| {
+            let mut segment = segment.to_string();
+            for regex in &self.citations {
+                segment = regex.replace_all(&segment, "").into_owned();
+            }
+            self.convert_math(&segment)
+        });
+
+        self.fix_lists(text.split('\n').map(String::from).collect())
+    }
+
+    fn convert_math(&self, text: &str) -> String {
+        let text = self.display_math.replace_all(text, |caps: &Captures| {
+            let inner = &caps[1];
+            // `\[1\]` 也可能只是转义的方括号：只有跨行或含 TeX 记号时才视为公式
+            if !inner.contains('\n') && !looks_like_tex(inner) {
+                return caps[0].to_string();
+            }
+            let body: Vec<&str> = inner.lines().filter(|l| !l.trim().is_empty()).collect();
+            format!("$${}$$", body.join("\n").trim())
+        });
+        self.inline_math
+            .replace_all(&text, |caps: &Captures| {
+                let inner = caps[1].trim();
+                if inner.is_empty() || inner.contains('$') {
+                    caps[0].to_string()
+                } else {
+                    format!("${}$", inner)
+                }
+            })
+            .into_owned()
+    }
+
+    /// pandoc 要求列表前有空行、子列表与父项内容对齐
+    fn fix_lists(&self, lines: Vec<String>) -> Vec<String> {
+        let mut output: Vec<String> = Vec::with_capacity(lines.len());
+        // (标记所在列, 内容所在列)
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        let mut prev_is_item = false;
+
+        for raw in lines {
+            let line = expand_leading_tabs(&raw);
+            if line.trim().is_empty() {
+                output.push(String::new());
+                prev_is_item = false;
+                continue;
+            }
+
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let item = self
+                .list_item
+                .captures(&line)
+                .filter(|_| !is_thematic_break(&line));
+
+            let Some(caps) = item else {
+                // 续行：缩进介于父项标记与内容之间时对齐到内容列
+                if indent == 0 {
+                    if !prev_is_item {
+                        stack.clear();
+                    }
+                    output.push(line);
+                } else {
+                    let target = stack
+                        .last()
+                        .filter(|(marker, content)| indent > *marker && indent < *content)
+                        .map(|(_, content)| *content)
+                        .unwrap_or(indent);
+                    output.push(format!("{}{}", " ".repeat(target), line.trim_start()));
+                }
+                continue;
+            };
+
+            while stack
+                .last()
+                .map(|(marker, _)| indent <= *marker)
+                .unwrap_or(false)
+            {
+                stack.pop();
+            }
+            let indent = match stack.last() {
+                Some((_, content)) if indent < *content => *content,
+                _ => indent,
+            };
+
+            let marker = match &caps[2] {
+                "•" | "●" | "▪" => "-",
+                other => other,
+            };
+            let content_col = indent + marker.chars().count() + 1;
+            stack.push((indent, content_col));
+
+            let previous_blank = output.last().map(|l| l.trim().is_empty()).unwrap_or(true);
+            if !previous_blank && !prev_is_item && stack.len() == 1 && !is_list_context(&output) {
+                output.push(String::new());
+            }
+
+            output.push(format!("{}{} {}", " ".repeat(indent), marker, &caps[4]));
+            prev_is_item = true;
+        }
+        output
+    }
+}
+
+/// 上一段落是否仍属于列表（列表项后紧跟的续行）
+fn is_list_context(output: &[String]) -> bool {
+    output
+        .iter()
+        .rev()
+        .take_while(|l| !l.trim().is_empty())
+        .any(|l| {
+            let trimmed = l.trim_start();
+            trimmed.starts_with("- ")
+                || trimmed.starts_with("* ")
+                || trimmed.starts_with("+ ")
+                || trimmed
+                    .split_once(['.', ')'])
+                    .map(|(n, rest)| {
+                        !n.is_empty()
+                            && n.chars().all(|c| c.is_ascii_digit())
+                            && rest.starts_with(' ')
+                    })
+                    .unwrap_or(false)
+        })
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && (compact.chars().all(|c| c == '-')
+            || compact.chars().all(|c| c == '*')
+            || compact.chars().all(|c| c == '_'))
+}
+
+fn expand_leading_tabs(line: &str) -> String {
+    let rest = line.trim_start_matches([' ', '\t']);
+    let leading = &line[..line.len() - rest.len()];
+    if !leading.contains('\t') {
+        return line.to_string();
+    }
+    let mut width = 0;
+    for ch in leading.chars() {
+        width = if ch == '\t' {
+            (width / 4 + 1) * 4
+        } else {
+            width + 1
+        };
+    }
+    format!("{}{}", " ".repeat(width), rest)
+}
+
+fn looks_like_tex(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            c,
+            '\\' | '^' | '_' | '=' | '{' | '}' | '+' | '<' | '>' | '|'
+        )
+    })
+}
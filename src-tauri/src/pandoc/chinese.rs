@@ -0,0 +1,128 @@
+use ferrous_opencc::{config::BuiltinConfig, OpenCC};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::cjk::{map_outside_code, map_prose, map_unprotected, PROTECTED_PATTERN};
+use super::frontmatter::front_matter_len;
+
+/// 简繁转换方向，词典随程序内置，无需联网
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChineseConversion {
+    /// 简体 → 繁体（OpenCC 标准）
+    S2t,
+    /// 简体 → 台湾正体
+    S2tw,
+    /// 简体 → 香港繁体
+    S2hk,
+    /// 繁体 → 简体
+    T2s,
+}
+
+impl ChineseConversion {
+    fn config(self) -> BuiltinConfig {
+        match self {
+            ChineseConversion::S2t => BuiltinConfig::S2t,
+            ChineseConversion::S2tw => BuiltinConfig::S2tw,
+            ChineseConversion::S2hk => BuiltinConfig::S2hk,
+            ChineseConversion::T2s => BuiltinConfig::T2s,
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            ChineseConversion::S2t => "s2t",
+            ChineseConversion::S2tw => "s2tw",
+            ChineseConversion::S2hk => "s2hk",
+            ChineseConversion::T2s => "t2s",
+        }
+    }
+}
+
+pub struct ChineseConverter {
+    conversion: ChineseConversion,
+    opencc: OpenCC,
+    protected: Regex,
+}
+
+impl ChineseConverter {
+    pub fn new(conversion: ChineseConversion) -> Result<Self, String> {
+        let opencc = OpenCC::from_config(conversion.config())
+            .map_err(|e| format!("Failed to load Chinese conversion dictionary: {}", e))?;
+        let protected =
+            Regex::new(PROTECTED_PATTERN).map_err(|e| format!("Failed to compile regex: {}", e))?;
+        Ok(Self {
+            conversion,
+            opencc,
+            protected,
+        })
+    }
+
+    /// 转换 Markdown：front matter 整体转换（键名均为 ASCII，不受影响），
+    /// 正文跳过代码块、行内代码、公式（含跨行的 `$$` 块与数学环境）、链接目标与网址
+    pub fn convert_markdown(&self, content: &str) -> String {
+        let (front_matter, body) = content.split_at(front_matter_len(content));
+        let body = map_prose(body, |text| {
+            map_outside_code(text, |segment| {
+                map_unprotected(segment, &self.protected, |plain| self.opencc.convert(plain))
+            })
+        });
+        format!("{}{}", self.opencc.convert(front_matter), body)
+    }
+
+    /// 元数据文件（YAML/JSON）整体转换，写到 `target_dir` 下（原文件可能位于只读的资源目录）
+    pub fn convert_metadata_file(&self, path: &Path, target_dir: &Path) -> Result<PathBuf, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read metadata file: {}", e))?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("yaml");
+        let name = sibling_path(path, self.conversion, ext);
+        let target = target_dir.join(name.file_name().unwrap_or_default());
+        fs::write(&target, self.opencc.convert(&content))
+            .map_err(|e| format!("Failed to write converted metadata: {}", e))?;
+        Ok(target)
+    }
+}
+
+fn sibling_path(path: &Path, conversion: ChineseConversion, ext: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string());
+    path.with_file_name(format!("{}.{}.{}", stem, conversion.suffix(), ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s2t(content: &str) -> String {
+        ChineseConverter::new(ChineseConversion::S2t)
+            .unwrap()
+            .convert_markdown(content)
+    }
+
+    #[test]
+    fn prose_and_front_matter_are_converted() {
+        assert_eq!(
+            s2t("---\ntitle: 简体\n---\n这是简体字\n"),
+            "---\ntitle: 簡體\n---\n這是簡體字\n"
+        );
+    }
+
+    #[test]
+    fn code_and_math_are_untouched() {
+        let text = "`简体` 与 $简体$\n\n$$\n\\text{简体}\n$$\n\n\\begin{align*}\n\\text{简体}\n\\end{align*}\n\n```\n简体\n```\n后文简体\n";
+        let expected = "`简体` 與 $简体$\n\n$$\n\\text{简体}\n$$\n\n\\begin{align*}\n\\text{简体}\n\\end{align*}\n\n```\n简体\n```\n後文簡體\n";
+        assert_eq!(s2t(text), expected);
+    }
+
+    #[test]
+    fn unknown_environments_are_converted() {
+        assert_eq!(
+            s2t("\\begin{quote}\n简体\n\\end{quote}\n"),
+            "\\begin{quote}\n簡體\n\\end{quote}\n"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+use regex::{Captures, Regex};
+use std::ops::Range;
+
+use super::frontmatter::front_matter_len;
+use super::tables::{find_fence_close, parse_fence_open};
+
+/// 行内不应改写的片段：链接/图片目标、网址、HTML 标签、行内公式
+pub(crate) const PROTECTED_PATTERN: &str =
+    r"\]\([^)\n]*\)|<[^<>\n]+>|(?:https?|ftp)://[^\s<>()\[\]]+|www\.[^\s<>()\[\]]+|\$[^$\n]+\$";
+
+/// 半角标点在中文语境下对应的全角标点
+const FULL_WIDTH_PUNCTUATION: [(char, char); 6] = [
+    (',', '，'),
+    (';', '；'),
+    (':', '：'),
+    ('!', '！'),
+    ('?', '？'),
+    ('.', '。'),
+];
+
+/// 中文排版预处理的各项开关，全部关闭时内容原样返回
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CjkTypesetting {
+    /// 修正紧贴中文或标点的强调（`**重点**，`、`*注意*：`、`这是**“重点”**内容`），
+    /// 使其能被 pandoc 识别
+    pub emphasis: bool,
+    /// 统一中文语境下的全角/半角标点与全角字母数字
+    pub punctuation: bool,
+    /// 中文与英文、数字之间加空格
+    pub spacing: bool,
+}
+
+/// 中文排版预处理，代码、公式、链接与网址不做改动
+pub fn typeset_cjk(content: &str, options: CjkTypesetting) -> Result<String, String> {
+    if !(options.emphasis || options.punctuation || options.spacing) {
+        return Ok(content.to_string());
+    }
+    let typesetter = Typesetter::new()?;
+    Ok(map_prose(content, |text| {
+        text.split('\n')
+            .map(|line| typesetter.line(line, options))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }))
+}
+
+struct Typesetter {
+    protected: Regex,
+    parenthesis: Regex,
+}
+
+impl Typesetter {
+    fn new() -> Result<Self, String> {
+        Ok(Self {
+            protected: compile(PROTECTED_PATTERN)?,
+            parenthesis: compile(r"\(([^()\n]*)\)")?,
+        })
+    }
+
+    fn line(&self, line: &str, options: CjkTypesetting) -> String {
+        let trimmed = line.trim_start();
+        // HTML 块、`!include`、链接定义原样保留
+        if trimmed.starts_with('<')
+            || trimmed.starts_with("!include")
+            || is_link_definition(trimmed)
+        {
+            return line.to_string();
+        }
+        // 表格行改变字符宽度会破坏网格表格的对齐，只修正强调
+        let in_table = trimmed.starts_with('|') || trimmed.starts_with('+');
+
+        map_outside_code(line, |segment| {
+            map_unprotected(segment, &self.protected, |text| {
+                let mut text = text.to_string();
+                if options.emphasis {
+                    text = fix_emphasis(&text);
+                }
+                if options.punctuation && !in_table {
+                    text = self.normalize_punctuation(&text);
+                }
+                if options.spacing && !in_table {
+                    text = add_spacing(&text);
+                }
+                text
+            })
+        })
+    }
+
+    fn normalize_punctuation(&self, text: &str) -> String {
+        let text: String = text.chars().map(to_half_width_alphanumeric).collect();
+
+        // 中文后的半角标点：后面是中文或行尾时改为全角，并去掉多余空格
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let full = FULL_WIDTH_PUNCTUATION
+                .iter()
+                .find(|(half, _)| *half == c)
+                .map(|(_, full)| *full);
+
+            if let Some(full) = full {
+                let prev_is_cjk = i > 0 && is_cjk(chars[i - 1]);
+                let mut next = i + 1;
+                while next < chars.len() && chars[next] == ' ' {
+                    next += 1;
+                }
+                let next_ok = next == chars.len() || is_cjk(chars[next]);
+                // `中文...` 之类的连续标点不处理
+                let repeated = chars.get(i + 1) == Some(&c);
+                if prev_is_cjk && next_ok && !repeated {
+                    result.push(full);
+                    i = next;
+                    continue;
+                }
+            }
+            result.push(c);
+            i += 1;
+        }
+
+        // 含中文的括号改为全角，`[文字](链接)` 的目标已在保护范围内
+        self.parenthesis
+            .replace_all(&result, |caps: &Captures| {
+                let start = caps.get(0).map(|m| m.start()).unwrap_or(0);
+                let before = result[..start].chars().next_back();
+                let inner = &caps[1];
+                if before == Some(']') || !inner.chars().any(is_cjk) {
+                    caps[0].to_string()
+                } else {
+                    format!("（{}）", inner)
+                }
+            })
+            .into_owned()
+    }
+}
+
+/// `这是**“重点”**内容` -> `这是“**重点**”内容`，`中文_强调_中文` -> `中文*强调*中文`
+///
+/// CommonMark 要求定界符内侧是标点时外侧必须是空白或标点，中文里几乎总不满足；
+/// 下划线在词内不能构成强调，紧贴中文时改用星号。只改写成对的 `*`/`**`/`_`/`__`。
+pub(crate) fn fix_emphasis(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+
+    // 连续的定界符串：(起始位置, 长度, 字符)
+    let mut runs: Vec<(usize, usize, char)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '*' || chars[i] == '_' {
+            let start = i;
+            while i < chars.len() && chars[i] == chars[start] {
+                i += 1;
+            }
+            runs.push((start, i - start, chars[start]));
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    let mut r = 0;
+    while r < runs.len() {
+        let (open, len, marker) = runs[r];
+        let close = runs[r + 1..]
+            .iter()
+            .position(|&(_, l, c)| l == len && c == marker)
+            .map(|p| p + r + 1);
+        let Some(k) = close.filter(|_| len <= 2) else {
+            r += 1;
+            continue;
+        };
+
+        let close_start = runs[k].0;
+        let body: String = chars[open + len..close_start].iter().collect();
+        if body.trim().is_empty() || body != body.trim() {
+            r += 1;
+            continue;
+        }
+
+        let before = open.checked_sub(1).map(|p| chars[p]);
+        let after = chars.get(close_start + len).copied();
+        if let Some(rewritten) = rewrite_emphasis(before, &body, after, marker, len) {
+            result.extend(&chars[cursor..open]);
+            result.push_str(&rewritten);
+            cursor = close_start + len;
+        }
+        r = k + 1;
+    }
+    result.extend(&chars[cursor..]);
+    result
+}
+
+/// 需要改写时返回新的强调文本
+fn rewrite_emphasis(
+    before: Option<char>,
+    body: &str,
+    after: Option<char>,
+    marker: char,
+    len: usize,
+) -> Option<String> {
+    let mut delimiter = marker.to_string().repeat(len);
+    if marker == '_' {
+        // snake_case 等英文标识符不能动，只处理紧贴中文的下划线强调
+        let is_latin = |c: Option<char>| c.map(|c| c.is_ascii_alphanumeric()).unwrap_or(false);
+        if is_latin(before) || is_latin(after) {
+            return None;
+        }
+        if !before.map(is_cjk).unwrap_or(false) && !after.map(is_cjk).unwrap_or(false) {
+            return None;
+        }
+        delimiter = "*".repeat(len);
+    }
+
+    let mut body = body;
+    let mut lead = "";
+    if before.map(is_word_char).unwrap_or(false) {
+        let split = body.len() - body.trim_start_matches(is_punctuation).len();
+        lead = &body[..split];
+        body = &body[split..];
+    }
+    let mut trail = "";
+    if after.map(is_word_char).unwrap_or(false) {
+        let split = body.trim_end_matches(is_punctuation).len();
+        trail = &body[split..];
+        body = &body[..split];
+    }
+
+    let unchanged = lead.is_empty() && trail.is_empty() && marker == '*';
+    if unchanged || body.trim().is_empty() || body != body.trim() {
+        return None;
+    }
+    Some(format!("{lead}{d}{body}{d}{trail}", d = delimiter))
+}
+
+/// 中文与英文、数字之间加空格
+fn add_spacing(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 8);
+    let mut prev: Option<char> = None;
+    for c in text.chars() {
+        if let Some(p) = prev {
+            let boundary = (is_cjk(p) && c.is_ascii_alphanumeric())
+                || (p.is_ascii_alphanumeric() && is_cjk(c));
+            if boundary {
+                result.push(' ');
+            }
+        }
+        result.push(c);
+        prev = Some(c);
+    }
+    result
+}
+
+fn to_half_width_alphanumeric(c: char) -> char {
+    match c {
+        '\u{FF10}'..='\u{FF19}' | '\u{FF21}'..='\u{FF3A}' | '\u{FF41}'..='\u{FF5A}' => {
+            char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[')
+        && line
+            .find("]:")
+            .map(|idx| !line[1..idx].contains(']'))
+            .unwrap_or(false)
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e))
+}
+
+/// 对代码块与公式块以外的正文分段应用 `f`，开头的 YAML front matter 原样保留
+///
+/// 原样保留的块：围栏代码块、缩进代码块、跨行的 `$$ … $$` 公式以及 `\begin{equation}` 等数学环境。
+/// `f` 收到的正文以 `\n` 分行，结果按原文的换行符写回，CRLF 文件仍为 CRLF。
+pub(crate) fn map_prose(content: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let (front_matter, body) = content.split_at(front_matter_len(content));
+    let lines: Vec<&str> = body.lines().collect();
+    let endings: Vec<&str> = body.split_inclusive('\n').map(line_ending).collect();
+    let mut result = front_matter.to_string();
+    let mut prose_start = 0;
+    let mut i = 0;
+
+    while i < lines.len() {
+        if let Some(close) = protected_block_end(&lines, i) {
+            push_prose(
+                &mut result,
+                &lines[prose_start..i],
+                &endings[prose_start..i],
+                &mut f,
+            );
+            for (line, ending) in lines[i..=close].iter().zip(&endings[i..=close]) {
+                result.push_str(line);
+                result.push_str(ending);
+            }
+            i = close + 1;
+            prose_start = i;
+            continue;
+        }
+        i += 1;
+    }
+    push_prose(
+        &mut result,
+        &lines[prose_start..],
+        &endings[prose_start..],
+        &mut f,
+    );
+    result
+}
+
+/// 一段连续正文交给 `f` 处理后写入 `result`，换行符沿用该段第一行的
+fn push_prose(
+    result: &mut String,
+    lines: &[&str],
+    endings: &[&str],
+    f: &mut impl FnMut(&str) -> String,
+) {
+    let (Some(first), Some(last)) = (endings.first(), endings.last()) else {
+        return;
+    };
+    let newline = if first.is_empty() { "\n" } else { first };
+    result.push_str(&f(&lines.join("\n")).replace('\n', newline));
+    result.push_str(last);
+}
+
+fn line_ending(line: &str) -> &'static str {
+    if line.ends_with("\r\n") {
+        "\r\n"
+    } else if line.ends_with('\n') {
+        "\n"
+    } else {
+        ""
+    }
+}
+
+/// 从第 `i` 行开始的代码块或公式块的最后一行；不是块的开头时返回 None
+fn protected_block_end(lines: &[&str], i: usize) -> Option<usize> {
+    if let Some((fence_char, fence_len, _)) = parse_fence_open(lines[i]) {
+        if let Some(close) = find_fence_close(lines, i + 1, fence_char, fence_len) {
+            return Some(close);
+        }
+    }
+    display_math_end(lines, i)
+        .or_else(|| math_environment_end(lines, i))
+        .or_else(|| indented_code_end(lines, i))
+}
+
+/// 本行的 `$$` 没有闭合时，到下一个包含 `$$` 的行为止都属于公式
+fn display_math_end(lines: &[&str], i: usize) -> Option<usize> {
+    if lines[i].matches("$$").count().is_multiple_of(2) {
+        return None;
+    }
+    (i + 1..lines.len()).find(|&j| lines[j].contains("$$"))
+}
+
+/// 数学环境（pandoc 按 TeX 公式处理），到对应的 `\end{…}` 为止
+const MATH_ENVIRONMENTS: [&str; 10] = [
+    "equation",
+    "align",
+    "alignat",
+    "gather",
+    "multline",
+    "flalign",
+    "eqnarray",
+    "math",
+    "displaymath",
+    "split",
+];
+
+fn math_environment_end(lines: &[&str], i: usize) -> Option<usize> {
+    let rest = lines[i].trim_start().strip_prefix("\\begin{")?;
+    let name = &rest[..rest.find('}')?];
+    if !MATH_ENVIRONMENTS.contains(&name.trim_end_matches('*')) {
+        return None;
+    }
+    let end = format!("\\end{{{}}}", name);
+    (i..lines.len()).find(|&j| {
+        let line = if j == i { rest } else { lines[j] };
+        line.contains(&end)
+    })
+}
+
+/// 缩进代码块：前一行为空、之前的正文不是列表项或缩进内容（否则属于列表的续行），
+/// 之后连续的缩进行（中间可有空行）都属于该块
+fn indented_code_end(lines: &[&str], i: usize) -> Option<usize> {
+    if !is_indented_code(lines[i]) || lines[i].trim().is_empty() {
+        return None;
+    }
+    if i > 0 && !lines[i - 1].trim().is_empty() {
+        return None;
+    }
+    let previous = lines[..i].iter().rev().find(|l| !l.trim().is_empty());
+    if previous.is_some_and(|l| l.starts_with([' ', '\t']) || is_list_item(l)) {
+        return None;
+    }
+
+    let mut end = i;
+    for (j, line) in lines.iter().enumerate().skip(i + 1) {
+        if is_indented_code(line) && !line.trim().is_empty() {
+            end = j;
+        } else if !line.trim().is_empty() {
+            break;
+        }
+    }
+    Some(end)
+}
+
+fn is_indented_code(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix(['-', '*', '+']) {
+        return rest.starts_with([' ', '\t']);
+    }
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    digits > 0 && trimmed[digits..].starts_with(['.', ')'])
+}
+
+/// 对行内代码以外的部分应用 `f`
+pub(crate) fn map_outside_code(text: &str, f: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut plain_start = 0;
+    for span in inline_code_spans(text) {
+        result.push_str(&f(&text[plain_start..span.start]));
+        result.push_str(&text[span.clone()]);
+        plain_start = span.end;
+    }
+    result.push_str(&f(&text[plain_start..]));
+    result
+}
+
+/// 行内代码（含两端反引号）在 `text` 中的位置
+pub(crate) fn inline_code_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find('`') {
+        let open = pos + offset;
+        let run = text[open..].len() - text[open..].trim_start_matches('`').len();
+        let fence = &text[open..open + run];
+
+        // 寻找等长的反引号串作为结束
+        let mut search = open + run;
+        let mut close = None;
+        while let Some(found) = text[search..].find(fence) {
+            let start = search + found;
+            let end = start + run;
+            let longer = text[end..].starts_with('`');
+            if !longer {
+                close = Some(end);
+                break;
+            }
+            search = end + text[end..].len() - text[end..].trim_start_matches('`').len();
+        }
+
+        match close {
+            Some(end) => {
+                spans.push(open..end);
+                pos = end;
+            }
+            None => pos = open + run,
+        }
+    }
+    spans
+}
+
+/// 对 `protected` 匹配以外的部分应用 `f`
+pub(crate) fn map_unprotected(text: &str, protected: &Regex, f: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for m in protected.find_iter(text) {
+        result.push_str(&f(&text[last..m.start()]));
+        result.push_str(m.as_str());
+        last = m.end();
+    }
+    result.push_str(&f(&text[last..]));
+    result
+}
+
+/// 汉字、假名、谚文及 CJK 兼容字符
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{2FDF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3100}'..='\u{312F}'
+        | '\u{31A0}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// 定界符外侧是字母/汉字时，内侧的标点会破坏强调识别
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !is_punctuation(c)
+}
+
+pub(crate) fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c,
+            '\u{2010}'..='\u{2027}'
+            | '\u{2030}'..='\u{205E}'
+            | '\u{3001}'..='\u{3003}'
+            | '\u{3008}'..='\u{3011}'
+            | '\u{3014}'..='\u{301F}'
+            | '\u{FF01}'..='\u{FF0F}'
+            | '\u{FF1A}'..='\u{FF20}'
+            | '\u{FF3B}'..='\u{FF40}'
+            | '\u{FF5B}'..='\u{FF65}'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: CjkTypesetting = CjkTypesetting {
+        emphasis: true,
+        punctuation: true,
+        spacing: true,
+    };
+
+    fn typeset(content: &str, options: CjkTypesetting) -> String {
+        typeset_cjk(content, options).unwrap()
+    }
+
+    #[test]
+    fn emphasis_fix_is_opt_in() {
+        let text = "这是**“重点”**内容，中文_强调_中文\n";
+        assert_eq!(typeset(text, CjkTypesetting::default()), text);
+        let emphasis = CjkTypesetting {
+            emphasis: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            typeset(text, emphasis),
+            "这是“**重点**”内容，中文*强调*中文\n"
+        );
+        assert_eq!(typeset("snake_case_name\n", emphasis), "snake_case_name\n");
+    }
+
+    #[test]
+    fn punctuation_and_spacing() {
+        assert_eq!(
+            typeset("使用Rust编写,速度很快!\n", ALL),
+            "使用 Rust 编写，速度很快！\n"
+        );
+        assert_eq!(
+            typeset("见[链接](http://a.com/x,y)与`a,b`\n", ALL),
+            "见[链接](http://a.com/x,y)与`a,b`\n"
+        );
+    }
+
+    #[test]
+    fn indented_code_is_untouched() {
+        let text = "说明:\n\n    let a = 中文,b;\n\n    x = 1!\n\n后文,结束\n";
+        assert_eq!(
+            typeset(text, ALL),
+            "说明：\n\n    let a = 中文,b;\n\n    x = 1!\n\n后文，结束\n"
+        );
+
+        // 列表项下的缩进行是续行，照常处理
+        let list = "- 第一项\n\n    续行,内容\n";
+        assert_eq!(typeset(list, ALL), "- 第一项\n\n    续行，内容\n");
+    }
+
+    #[test]
+    fn display_math_is_untouched() {
+        let text = "公式如下:\n$$\nf(x)=中文,x\n$$ {#eq:a}\n结束,好\n";
+        assert_eq!(
+            typeset(text, ALL),
+            "公式如下：\n$$\nf(x)=中文,x\n$$ {#eq:a}\n结束，好\n"
+        );
+        // 同一行闭合的公式不影响后面的正文
+        assert_eq!(typeset("$$a,中$$\n后文,好\n", ALL), "$$a,中$$\n后文，好\n");
+    }
+
+    #[test]
+    fn fenced_code_and_front_matter_are_untouched() {
+        let text = "---\ntitle: 标题,A\n---\n```\n中文,x\n```\n正文,好\n";
+        assert_eq!(
+            typeset(text, ALL),
+            "---\ntitle: 标题,A\n---\n```\n中文,x\n```\n正文，好\n"
+        );
+    }
+
+    #[test]
+    fn line_endings_are_preserved() {
+        let text = "第一行,好\r\n\r\n```\r\n代码,x\r\n```\r\n最后,好";
+        assert_eq!(
+            typeset(text, ALL),
+            "第一行，好\r\n\r\n```\r\n代码,x\r\n```\r\n最后，好"
+        );
+        assert_eq!(map_prose("a\r\nb\r\n", |t| t.to_string()), "a\r\nb\r\n");
+        assert_eq!(map_prose("a\nb", |t| t.to_string()), "a\nb");
+    }
+
+    #[test]
+    fn inline_code_spans_match_backtick_runs() {
+        let text = "a `b` c ``d ` e`` f `g";
+        let spans: Vec<&str> = inline_code_spans(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(spans, ["`b`", "``d ` e``"]);
+    }
+}
@@ -1,13 +1,22 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use serde_yaml::{Mapping as YamlMapping, Value as YamlValue};
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::SystemTime;
 use tauri::{AppHandle, Manager};
 
+use super::callouts::{render_callouts, CalloutStyles};
+use super::chinese::{ChineseConversion, ChineseConverter};
 use super::config::{get_crossref_executable_path, get_pandoc_executable_path};
+use super::frontmatter::{merge_front_matter, normalize_front_matter, MetadataPrecedence};
+use super::overrides::StyleOverrides;
+use super::runtime::StagedFile;
+use super::stylemap::{apply_custom_styles, CustomStyles};
+use super::styles::read_docx_styles;
+use super::templates::{stage_style_overrides, stage_template};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConvertOptions {
@@ -16,15 +25,53 @@ pub struct ConvertOptions {
     pub source_dir: Option<String>,
     pub source_name: Option<String>,
     pub reference_doc: Option<String>,
+    /// 未给出 `reference_doc` 时按模板 ID 准备参考文档，受保护模板在本次转换期间解密
+    #[serde(default)]
+    pub template_id: Option<String>,
     pub metadata: Option<Value>, // Pandoc 元数据对象
     pub metadata_file: Option<String>,
     pub use_crossref: bool,
+    /// 参考文献数据库，非空时启用 citeproc
+    #[serde(default)]
+    pub bibliography: Option<Vec<String>>,
+    /// 简繁转换，作用于正文与元数据
+    #[serde(default)]
+    pub chinese_conversion: Option<ChineseConversion>,
+    /// 文档 front matter 与预设元数据冲突时的优先级
+    #[serde(default)]
+    pub metadata_precedence: MetadataPrecedence,
+    /// 模板定义的 class → Word 样式映射，按参考文档中实际存在的样式校验
+    #[serde(default)]
+    pub custom_styles: Option<BTreeMap<String, String>>,
+    /// 本次转换的样式覆盖，写入参考文档的 runtime 副本，模板原文件不变
+    #[serde(default)]
+    pub style_overrides: Option<StyleOverrides>,
+    /// 模板包提供的 Lua 过滤器，在 crossref 之后、citeproc 之前依次运行
+    #[serde(default)]
+    pub lua_filters: Option<Vec<String>>,
+    /// 引文样式（CSL）
+    #[serde(default)]
+    pub csl: Option<String>,
 }
 
 pub async fn convert_md_to_docx(
     app: &AppHandle,
     options: ConvertOptions,
 ) -> Result<String, String> {
+    // runtime 目录中的模板在本次转换结束时删除，无论成功、失败还是提前返回
+    let mut staged: Vec<StagedFile> = options
+        .reference_doc
+        .as_deref()
+        .and_then(|doc| StagedFile::adopt(app, Path::new(doc)))
+        .into_iter()
+        .collect();
+    let mut base_reference_doc = options.reference_doc.clone();
+    if let (None, Some(template_id)) = (&base_reference_doc, &options.template_id) {
+        let (path, guard) = stage_template(app, template_id)?;
+        base_reference_doc = Some(path.to_string_lossy().to_string());
+        staged.extend(guard);
+    }
+
     let pandoc_exe = get_pandoc_executable_path(app)?;
 
     if !pandoc_exe.exists() {
@@ -33,9 +80,67 @@ pub async fn convert_md_to_docx(
 
     // 如果有元数据，先将其写入 Markdown 文件头部
     if let Some(metadata) = &options.metadata {
-        inject_metadata_to_markdown(&options.input_file, metadata)?;
+        inject_metadata_to_markdown(&options.input_file, metadata, options.metadata_precedence)?;
+    }
+
+    // 样式覆盖只写入 runtime 副本，之后的样式读取与 pandoc 都使用该副本
+    let mut reference_doc = base_reference_doc.clone();
+    if let (Some(doc), Some(overrides)) = (
+        &base_reference_doc,
+        options.style_overrides.as_ref().filter(|o| !o.is_empty()),
+    ) {
+        let patched = stage_style_overrides(app, Path::new(doc), overrides)?;
+        reference_doc = Some(patched.path().to_string_lossy().to_string());
+        staged.push(patched);
+    }
+
+    // 提示框与简繁转换在副本上进行，原文保持不变
+    let source = fs::read_to_string(&options.input_file)
+        .map_err(|e| format!("Failed to read markdown file: {}", e))?;
+    let doc_styles = match &reference_doc {
+        Some(doc) => read_docx_styles(Path::new(doc)).unwrap_or_else(|e| {
+            log::warn!("Failed to read styles from reference doc: {}", e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+
+    // 模板定义的 class → 样式映射先于提示框处理，显式映射优先
+    let mut prepared = source.clone();
+    if let Some(mapping) = options.custom_styles.as_ref().filter(|m| !m.is_empty()) {
+        let (custom_styles, issues) = CustomStyles::resolve(mapping, &doc_styles);
+        for issue in &issues {
+            log::warn!(
+                "Skipping custom style mapping .{} -> \"{}\": {}",
+                issue.class,
+                issue.style,
+                issue.message
+            );
+        }
+        prepared = apply_custom_styles(&prepared, &custom_styles)?;
+    }
+    prepared = render_callouts(&prepared, &CalloutStyles::from_styles(&doc_styles))?;
+    let mut metadata_file = options.metadata_file.as_ref().map(PathBuf::from);
+
+    // 简繁转换放在提示框之后，默认标题一并转换；front matter 中注入的元数据也一并转换
+    if let Some(conversion) = options.chinese_conversion {
+        let converter = ChineseConverter::new(conversion)?;
+        prepared = converter.convert_markdown(&prepared);
+        if let Some(metadata) = &metadata_file {
+            let target_dir = Path::new(&options.input_file)
+                .parent()
+                .unwrap_or(Path::new("."))
+                .to_path_buf();
+            metadata_file = Some(converter.convert_metadata_file(metadata, &target_dir)?);
+        }
     }
 
+    let input_file = if prepared == source {
+        PathBuf::from(&options.input_file)
+    } else {
+        write_working_copy(Path::new(&options.input_file), &prepared)?
+    };
+
     let output_path = resolve_output_path(&options);
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)
@@ -51,20 +156,19 @@ pub async fn convert_md_to_docx(
     }
 
     // 基本参数
-    cmd.arg(&options.input_file).arg("-o").arg(&output_path);
+    cmd.arg(&input_file).arg("-o").arg(&output_path);
 
-    if let Some(parent) = Path::new(&options.input_file).parent() {
+    if let Some(parent) = input_file.parent() {
         cmd.current_dir(parent);
     }
 
     // 参考文档（模板）
-    let reference_doc_path = options.reference_doc.clone();
-    if let Some(ref_doc) = &options.reference_doc {
+    if let Some(ref_doc) = &reference_doc {
         cmd.arg("--reference-doc").arg(ref_doc);
     }
 
     // 元数据文件
-    if let Some(metadata) = &options.metadata_file {
+    if let Some(metadata) = &metadata_file {
         cmd.arg("--metadata-file").arg(metadata);
     }
 
@@ -76,20 +180,27 @@ pub async fn convert_md_to_docx(
         }
     }
 
+    for filter in options.lua_filters.iter().flatten() {
+        cmd.arg("--lua-filter").arg(filter);
+    }
+
+    // 参考文献（citeproc 需在 crossref 之后运行）
+    if let Some(bibliography) = options.bibliography.as_ref().filter(|b| !b.is_empty()) {
+        cmd.arg("--citeproc");
+        for bib in bibliography {
+            cmd.arg("--bibliography").arg(bib);
+        }
+    }
+    if let Some(csl) = &options.csl {
+        cmd.arg("--csl").arg(csl);
+    }
+
     // 执行转换
     let output = cmd
         .output()
         .map_err(|e| format!("Failed to execute pandoc: {}", e))?;
 
     if output.status.success() {
-        // 转换成功后，删除 runtime 模板文件
-        if let Some(ref_doc) = reference_doc_path {
-            let ref_path = PathBuf::from(&ref_doc);
-            if ref_path.exists() && ref_path.to_string_lossy().contains("runtime") {
-                let _ = fs::remove_file(&ref_path); // 忽略删除错误
-            }
-        }
-
         // 清理旧的 session 目录（只保留最新5个）
         cleanup_old_sessions(app);
 
@@ -203,67 +314,48 @@ pub fn get_pandoc_version(app: &AppHandle) -> Result<String, String> {
 }
 
 /// 将元数据注入到 Markdown 文件的 YAML frontmatter
-fn inject_metadata_to_markdown(file_path: &str, metadata: &Value) -> Result<(), String> {
+fn inject_metadata_to_markdown(
+    file_path: &str,
+    metadata: &Value,
+    precedence: MetadataPrecedence,
+) -> Result<(), String> {
     // 读取原始 Markdown 内容
-    let content = fs::read_to_string(file_path)
+    let original = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read markdown file: {}", e))?;
+    // TOML/JSON front matter 先转为 YAML，避免注入后出现两个文件头
+    let content = normalize_front_matter(&original)?;
 
     // 如果元数据不是对象则跳过注入
     let metadata_obj = metadata.as_object().ok_or("Metadata is not an object")?;
+    let mapping = match serde_yaml::to_value(metadata_obj)
+        .map_err(|e| format!("Failed to convert metadata: {}", e))?
+    {
+        YamlValue::Mapping(mapping) => mapping,
+        _ => return Ok(()),
+    };
 
-    // 提取已有 frontmatter（如果存在）
-    let lines: Vec<&str> = content.split('\n').collect();
-    let mut existing_map = YamlMapping::new();
-    let mut body_start_idx = 0;
-
-    if lines.first().map(|l| l.trim()) == Some("---") {
-        if let Some(end_rel_idx) = lines.iter().skip(1).position(|l| l.trim() == "---") {
-            let end_idx = end_rel_idx + 1;
-            let frontmatter = lines[1..end_idx].join("\n");
-            body_start_idx = end_idx + 1;
-
-            if let Ok(parsed) = serde_yaml::from_str::<YamlMapping>(&frontmatter) {
-                existing_map = parsed;
-            }
-        }
-    }
-
-    // 将新元数据转换为 YAML Mapping 并覆盖现有键
-    if let Ok(new_meta_yaml) = serde_yaml::to_value(metadata_obj) {
-        if let YamlValue::Mapping(map) = new_meta_yaml {
-            for (k, v) in map {
-                existing_map.insert(k, v);
-            }
-        }
-    }
-
-    // 如果合并后为空则无需写回
-    if existing_map.is_empty() {
+    let merged = merge_front_matter(&content, &mapping, precedence)?;
+    if merged == original {
         return Ok(());
     }
 
-    let merged_yaml = serde_yaml::to_string(&YamlValue::Mapping(existing_map))
-        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
-
-    let body = if body_start_idx >= lines.len() {
-        String::new()
-    } else {
-        lines[body_start_idx..].join("\n")
-    };
-
-    let new_content = format!(
-        "---\n{}---\n\n{}",
-        merged_yaml,
-        body.trim_start_matches('\n')
-    );
-
     // 写回文件
-    fs::write(file_path, new_content)
-        .map_err(|e| format!("Failed to write markdown file: {}", e))?;
+    fs::write(file_path, merged).map_err(|e| format!("Failed to write markdown file: {}", e))?;
 
     Ok(())
 }
 
+/// 预处理后的 Markdown 写到同目录的 `<stem>.pandoc.md`，保持相对图片路径可用
+fn write_working_copy(input: &Path, content: &str) -> Result<PathBuf, String> {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string());
+    let target = input.with_file_name(format!("{}.pandoc.md", stem));
+    fs::write(&target, content).map_err(|e| format!("Failed to write prepared markdown: {}", e))?;
+    Ok(target)
+}
+
 /// 清理旧的 session 目录，只保留最新的5个
 fn cleanup_old_sessions(app: &AppHandle) {
     if let Ok(cache_dir) = app.path().cache_dir() {
@@ -0,0 +1,283 @@
+use aes::Aes256;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use cbc::Decryptor;
+use cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+use super::docx::DocxPackage;
+
+/// 受保护模板容器的文件头
+///
+/// ```text
+/// magic "FMTP" | version u8 | cipher u8 | kdf u8 | iterations u32 LE | salt_len u8 | salt | nonce[12] | ciphertext+tag
+/// ```
+/// 文件头整体作为 AEAD 的附加数据，篡改任何参数都会导致解密失败。
+const MAGIC: &[u8; 4] = b"FMTP";
+const VERSION: u8 = 1;
+const CIPHER_AES_256_GCM: u8 = 1;
+const KDF_PBKDF2_SHA256: u8 = 1;
+/// key 是应用或授权派生的高熵字符串而非用户口令，迭代次数只需兼顾打开模板的速度
+const DEFAULT_ITERATIONS: u32 = 100_000;
+/// 解密时接受的迭代次数上限，防止恶意文件拖慢启动
+const MAX_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// 按当前格式加密模板（随机盐与 nonce）
+pub fn encrypt_template(plain: &[u8], key_string: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(12 + SALT_LEN + NONCE_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&[VERSION, CIPHER_AES_256_GCM, KDF_PBKDF2_SHA256]);
+    header.extend_from_slice(&DEFAULT_ITERATIONS.to_le_bytes());
+    header.push(SALT_LEN as u8);
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let key = derive_key(key_string, &salt, DEFAULT_ITERATIONS);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Invalid key: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plain,
+                aad: &header,
+            },
+        )
+        .map_err(|_| "Failed to encrypt template".to_string())?;
+
+    header.extend_from_slice(&ciphertext);
+    Ok(header)
+}
+
+/// 加密后的模板文件信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtectedTemplate {
+    pub output_path: String,
+    pub plain_size: usize,
+    pub encrypted_size: usize,
+    /// 明文 .docx 的 SHA-256，便于核对发布的版本
+    pub sha256: String,
+}
+
+/// 加密 .docx 并写成 `stage_template` 读取的容器格式
+///
+/// 写入前后各做一次解密比对，确认用同一 key 能还原出原文件。
+pub fn protect_template(
+    input: &Path,
+    output: &Path,
+    key_string: &str,
+) -> Result<ProtectedTemplate, String> {
+    if key_string.is_empty() {
+        return Err("Encryption key cannot be empty".to_string());
+    }
+    let plain = fs::read(input).map_err(|e| format!("Failed to read template: {}", e))?;
+    if is_container(&plain) {
+        return Err("Template is already protected".to_string());
+    }
+    DocxPackage::from_bytes(&plain)?;
+
+    let encrypted = encrypt_template(&plain, key_string)?;
+    if decrypt_template(&encrypted, key_string)? != plain {
+        return Err("Round-trip verification failed before writing".to_string());
+    }
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    }
+    fs::write(output, &encrypted)
+        .map_err(|e| format!("Failed to write protected template: {}", e))?;
+    let written =
+        fs::read(output).map_err(|e| format!("Failed to read protected template: {}", e))?;
+    if decrypt_template(&written, key_string)? != plain {
+        let _ = fs::remove_file(output);
+        return Err("Round-trip verification failed after writing".to_string());
+    }
+
+    Ok(ProtectedTemplate {
+        output_path: output.to_string_lossy().to_string(),
+        plain_size: plain.len(),
+        encrypted_size: written.len(),
+        sha256: format!("{:x}", Sha256::digest(&plain)),
+    })
+}
+
+/// 解密受保护模板：识别容器格式，没有文件头的按旧版 AES-256-CBC 处理
+///
+/// key 错误会在这里直接报错，而不是产生无效的 .docx 留给 pandoc。
+pub fn decrypt_template(data: &[u8], key_string: &str) -> Result<Vec<u8>, String> {
+    if is_container(data) {
+        decrypt_container(data, key_string)
+    } else {
+        let plain = decrypt_legacy_cbc(data, key_string)?;
+        // 旧格式没有完整性校验，按 zip 文件头判断 key 是否正确
+        if !plain.starts_with(b"PK\x03\x04") {
+            return Err(wrong_key());
+        }
+        Ok(plain)
+    }
+}
+
+pub fn is_container(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn decrypt_container(data: &[u8], key_string: &str) -> Result<Vec<u8>, String> {
+    let truncated = || "Protected template is truncated".to_string();
+    let fixed = data.get(..12).ok_or_else(truncated)?;
+    let (version, cipher_id, kdf) = (fixed[4], fixed[5], fixed[6]);
+    if version != VERSION {
+        return Err(format!(
+            "Unsupported protected template version {}; please update the app",
+            version
+        ));
+    }
+    if cipher_id != CIPHER_AES_256_GCM || kdf != KDF_PBKDF2_SHA256 {
+        return Err(format!(
+            "Unsupported protected template cipher {} / kdf {}",
+            cipher_id, kdf
+        ));
+    }
+    let iterations = u32::from_le_bytes([fixed[7], fixed[8], fixed[9], fixed[10]]);
+    if iterations == 0 || iterations > MAX_ITERATIONS {
+        return Err(format!("Invalid key derivation parameters: {}", iterations));
+    }
+
+    let salt_len = fixed[11] as usize;
+    let header_len = 12 + salt_len + NONCE_LEN;
+    let header = data.get(..header_len).ok_or_else(truncated)?;
+    let salt = &header[12..12 + salt_len];
+    let nonce = &header[12 + salt_len..];
+
+    let key = derive_key(key_string, salt, iterations);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Invalid key: {}", e))?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: &data[header_len..],
+                aad: header,
+            },
+        )
+        .map_err(|_| wrong_key())
+}
+
+fn derive_key(key_string: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(key_string.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+fn wrong_key() -> String {
+    "Wrong key: the protected template could not be decrypted".to_string()
+}
+
+/// 旧格式：IV(16) + AES-256-CBC 密文，key 为 key 字符串的 SHA-256
+fn decrypt_legacy_cbc(data: &[u8], key_string: &str) -> Result<Vec<u8>, String> {
+    if data.len() < 16 {
+        return Err("Encrypted content is too short".to_string());
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(key_string.as_bytes());
+    let key_bytes = hasher.finalize();
+
+    let (iv, ciphertext) = data.split_at(16);
+    let mut buffer = ciphertext.to_vec();
+    let decrypted = Decryptor::<Aes256>::new((&key_bytes).into(), iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+        .map_err(|_| wrong_key())?;
+
+    Ok(decrypted.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cbc::Encryptor;
+    use cipher::BlockEncryptMut;
+
+    const KEY: &str = "test-template-key";
+
+    /// 以 zip 文件头开头的明文，与真实 .docx 一样能通过旧格式的校验
+    fn sample_docx() -> Vec<u8> {
+        let mut plain = b"PK\x03\x04".to_vec();
+        plain.extend((0..200u8).cycle().take(1000));
+        plain
+    }
+
+    #[test]
+    fn round_trip() {
+        let plain = sample_docx();
+        let encrypted = encrypt_template(&plain, KEY).unwrap();
+        assert!(is_container(&encrypted));
+        assert_eq!(decrypt_template(&encrypted, KEY).unwrap(), plain);
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let encrypted = encrypt_template(&sample_docx(), KEY).unwrap();
+        assert_eq!(decrypt_template(&encrypted, "other-key"), Err(wrong_key()));
+    }
+
+    #[test]
+    fn tampered_header_and_tag_are_rejected() {
+        let encrypted = encrypt_template(&sample_docx(), KEY).unwrap();
+
+        // 盐属于文件头（附加数据）
+        let mut header = encrypted.clone();
+        header[13] ^= 0x01;
+        assert_eq!(decrypt_template(&header, KEY), Err(wrong_key()));
+
+        let mut tag = encrypted.clone();
+        let last = tag.len() - 1;
+        tag[last] ^= 0x01;
+        assert_eq!(decrypt_template(&tag, KEY), Err(wrong_key()));
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let encrypted = encrypt_template(&sample_docx(), KEY).unwrap();
+        for len in [4, 11, 12 + SALT_LEN] {
+            let err = decrypt_template(&encrypted[..len], KEY).unwrap_err();
+            assert!(err.contains("truncated"), "{}: {}", len, err);
+        }
+    }
+
+    #[test]
+    fn excessive_iterations_are_rejected() {
+        let mut encrypted = encrypt_template(&sample_docx(), KEY).unwrap();
+        encrypted[7..11].copy_from_slice(&(MAX_ITERATIONS + 1).to_le_bytes());
+        let err = decrypt_template(&encrypted, KEY).unwrap_err();
+        assert!(err.contains("Invalid key derivation parameters"), "{}", err);
+    }
+
+    #[test]
+    fn legacy_cbc_still_decrypts() {
+        let plain = sample_docx();
+        let key = Sha256::digest(KEY.as_bytes());
+        let iv = [7u8; 16];
+        let mut buffer = plain.clone();
+        buffer.resize(plain.len() + 16, 0);
+        let ciphertext = Encryptor::<Aes256>::new((&key).into(), (&iv).into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, plain.len())
+            .unwrap()
+            .to_vec();
+        let mut legacy = iv.to_vec();
+        legacy.extend_from_slice(&ciphertext);
+
+        assert!(!is_container(&legacy));
+        assert_eq!(decrypt_template(&legacy, KEY).unwrap(), plain);
+        assert!(decrypt_template(&legacy, "other-key").is_err());
+    }
+}
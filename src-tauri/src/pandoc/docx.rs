@@ -0,0 +1,94 @@
+use std::io::{Cursor, Read, Write};
+
+const CONTENT_TYPES: &str = "[Content_Types].xml";
+
+/// 内存中的 .docx 包，按部件读写后重新打包
+#[derive(Default)]
+pub struct DocxPackage {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl DocxPackage {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| format!("Invalid docx file: {}", e))?;
+
+        let mut entries = Vec::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|e| format!("Failed to read docx entry: {}", e))?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)
+                .map_err(|e| format!("Failed to read {} from docx: {}", file.name(), e))?;
+            entries.push((file.name().to_string(), data));
+        }
+
+        if !entries.iter().any(|(name, _)| name == CONTENT_TYPES) {
+            return Err("Invalid docx file: missing [Content_Types].xml".to_string());
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn part_names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn has_part(&self, name: &str) -> bool {
+        self.entries.iter().any(|(n, _)| n == name)
+    }
+
+    /// 读取 XML 部件
+    pub fn part(&self, name: &str) -> Option<String> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, data)| String::from_utf8_lossy(data).into_owned())
+    }
+
+    /// 写入部件，不存在时新增
+    pub fn set_part(&mut self, name: &str, content: impl Into<Vec<u8>>) {
+        let content = content.into();
+        match self.entries.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = content,
+            None => self.entries.push((name.to_string(), content)),
+        }
+    }
+
+    pub fn remove_part(&mut self, name: &str) {
+        self.entries.retain(|(n, _)| n != name);
+    }
+
+    /// 重新打包；`[Content_Types].xml` 放在首位，与 Word 的写法一致
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut writer = zip::ZipWriter::new(&mut buffer);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        let ordered = self
+            .entries
+            .iter()
+            .filter(|(name, _)| name == CONTENT_TYPES)
+            .chain(
+                self.entries
+                    .iter()
+                    .filter(|(name, _)| name != CONTENT_TYPES),
+            );
+        for (name, data) in ordered {
+            writer
+                .start_file(name.as_str(), options)
+                .map_err(|e| format!("Failed to write docx entry {}: {}", name, e))?;
+            writer
+                .write_all(data)
+                .map_err(|e| format!("Failed to write docx entry {}: {}", name, e))?;
+        }
+        writer
+            .finish()
+            .map_err(|e| format!("Failed to finish docx: {}", e))?;
+        Ok(buffer.into_inner())
+    }
+}
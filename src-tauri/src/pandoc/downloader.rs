@@ -101,7 +101,7 @@ pub async fn extract_archive(archive_path: &Path, extract_to: &Path) -> Result<(
     Ok(())
 }
 
-fn extract_zip(archive_path: &Path, extract_to: &Path) -> Result<(), String> {
+pub(crate) fn extract_zip(archive_path: &Path, extract_to: &Path) -> Result<(), String> {
     let file = File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
 
     let mut archive =
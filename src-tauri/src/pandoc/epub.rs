@@ -0,0 +1,120 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::input::percent_decode;
+
+/// 解压后的 EPUB 包信息
+pub struct EpubPackage {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    /// 按 spine 顺序排列的章节（相对解压目录）
+    pub chapters: Vec<String>,
+}
+
+/// 读取 `META-INF/container.xml` 指向的 OPF，按 spine 顺序列出正文章节
+///
+/// 跳过 `linear="no"` 的条目和导航文档（nav/toc），只保留 XHTML/HTML。
+pub fn read_epub_package(extract_dir: &Path) -> Result<EpubPackage, String> {
+    let container = fs::read_to_string(extract_dir.join("META-INF").join("container.xml"))
+        .map_err(|e| format!("Invalid EPUB, container.xml missing: {}", e))?;
+    let opf_rel = capture(
+        &container,
+        r#"(?is)<rootfile\b[^>]*\bfull-path\s*=\s*"([^"]+)""#,
+    )?
+    .ok_or_else(|| "Invalid EPUB, no rootfile in container.xml".to_string())?;
+
+    let opf_path = extract_dir.join(percent_decode(&opf_rel));
+    let opf =
+        fs::read_to_string(&opf_path).map_err(|e| format!("Failed to read EPUB package: {}", e))?;
+    let opf_dir = Path::new(&opf_rel).parent().unwrap_or(Path::new(""));
+
+    let tag_regex = |name: &str| {
+        Regex::new(&format!(r"(?is)<{}\b[^>]*>", name))
+            .map_err(|e| format!("Failed to compile regex: {}", e))
+    };
+
+    // manifest: id -> (href, media-type, properties)
+    let mut manifest: HashMap<String, (String, String, String)> = HashMap::new();
+    for tag in tag_regex("item")?.find_iter(&opf) {
+        let attrs = parse_attributes(tag.as_str())?;
+        if let (Some(id), Some(href)) = (attrs.get("id"), attrs.get("href")) {
+            manifest.insert(
+                id.clone(),
+                (
+                    href.clone(),
+                    attrs.get("media-type").cloned().unwrap_or_default(),
+                    attrs.get("properties").cloned().unwrap_or_default(),
+                ),
+            );
+        }
+    }
+
+    let mut chapters = Vec::new();
+    for tag in tag_regex("itemref")?.find_iter(&opf) {
+        let attrs = parse_attributes(tag.as_str())?;
+        if attrs.get("linear").map(|l| l == "no").unwrap_or(false) {
+            continue;
+        }
+        let Some((href, media_type, properties)) =
+            attrs.get("idref").and_then(|id| manifest.get(id))
+        else {
+            continue;
+        };
+        let is_html =
+            media_type.contains("html") || href.ends_with(".html") || href.ends_with(".htm");
+        if !is_html || properties.split_whitespace().any(|p| p == "nav") {
+            continue;
+        }
+
+        let rel = opf_dir.join(percent_decode(href.split('#').next().unwrap_or(href)));
+        let rel = rel.to_string_lossy().to_string();
+        if extract_dir.join(&rel).is_file() && !chapters.contains(&rel) {
+            chapters.push(rel);
+        }
+    }
+
+    if chapters.is_empty() {
+        return Err("No readable chapters found in EPUB".to_string());
+    }
+
+    let title = capture(&opf, r"(?is)<dc:title\b[^>]*>(.*?)</dc:title>")?;
+    let creator_regex = Regex::new(r"(?is)<dc:creator\b[^>]*>(.*?)</dc:creator>")
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+    let authors = creator_regex
+        .captures_iter(&opf)
+        .map(|c| c[1].trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+
+    Ok(EpubPackage {
+        title,
+        authors,
+        chapters,
+    })
+}
+
+fn capture(text: &str, pattern: &str) -> Result<Option<String>, String> {
+    let regex = Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e))?;
+    Ok(regex
+        .captures(text)
+        .map(|c| c[1].trim().to_string())
+        .filter(|v| !v.is_empty()))
+}
+
+fn parse_attributes(tag: &str) -> Result<HashMap<String, String>, String> {
+    let regex = Regex::new(r#"([A-Za-z_:][\w:.-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+    Ok(regex
+        .captures_iter(tag)
+        .map(|c| {
+            let value = c
+                .get(2)
+                .or_else(|| c.get(3))
+                .map(|m| m.as_str())
+                .unwrap_or("");
+            (c[1].to_string(), value.to_string())
+        })
+        .collect())
+}
@@ -0,0 +1,410 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use serde_yaml::{Mapping as YamlMapping, Value as YamlValue};
+
+/// 文档 front matter 与模板预设元数据同名时以谁为准
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataPrecedence {
+    /// 模板预设覆盖文档中的同名字段
+    #[default]
+    Preset,
+    /// 文档已有的字段优先，预设只补充缺失的字段
+    Document,
+}
+
+/// 将 Hugo 等使用的 TOML（`+++`）与 JSON（`{ }`）front matter 转为 YAML（`---`）
+///
+/// pandoc 只识别 YAML 元数据块，其他格式会被当成正文。已是 YAML 或没有 front matter 时原样返回。
+pub fn normalize_front_matter(content: &str) -> Result<String, String> {
+    // 带 BOM 的文件开头不是 `+++`/`{`，先去掉
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let converted = if content.starts_with("+++") {
+        toml_front_matter(content)?
+    } else if content.starts_with('{') {
+        json_front_matter(content)?
+    } else {
+        None
+    };
+
+    Ok(match converted {
+        Some((mapping, body)) => {
+            let yaml = yaml_block(&mapping)?;
+            format!("{}\n\n{}", yaml, body.trim_start_matches(['\r', '\n']))
+        }
+        None => content.to_string(),
+    })
+}
+
+/// 开头 YAML front matter（含结束行）的字节长度，没有时为 0
+///
+/// 结束行可以是 `---` 或 `...`，兼容 CRLF 换行。
+pub(crate) fn front_matter_len(content: &str) -> usize {
+    let mut lines = content.split_inclusive('\n');
+    let first = match lines.next() {
+        Some(first) if first.trim_end() == "---" => first,
+        _ => return 0,
+    };
+
+    let mut len = first.len();
+    for line in lines {
+        len += line.len();
+        if matches!(line.trim_end(), "---" | "...") {
+            return len;
+        }
+    }
+    0
+}
+
+/// 把元数据合并进 Markdown 的 YAML front matter
+///
+/// 只改写需要变更的顶层字段，其余行（注释、字段顺序、引号风格）保持原样，新增字段追加在末尾。
+/// 已有 front matter 不是合法 YAML 时返回错误，而不是静默丢弃。
+pub fn merge_front_matter(
+    content: &str,
+    metadata: &YamlMapping,
+    precedence: MetadataPrecedence,
+) -> Result<String, String> {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let len = front_matter_len(content);
+
+    if len == 0 {
+        if metadata.is_empty() {
+            return Ok(content.to_string());
+        }
+        let yaml = yaml_lines(metadata, newline)?.concat();
+        return Ok(format!(
+            "---{nl}{yaml}---{nl}{nl}{body}",
+            nl = newline,
+            yaml = yaml,
+            body = content.trim_start_matches(['\r', '\n'])
+        ));
+    }
+
+    let (block, body) = content.split_at(len);
+    let mut lines: Vec<String> = block.split_inclusive('\n').map(String::from).collect();
+    let opener = lines.remove(0);
+    let closer = lines.pop().unwrap_or_default();
+    let existing = parse_mapping(&lines.concat())?;
+
+    for (key, value) in metadata {
+        let entry = YamlMapping::from_iter([(key.clone(), value.clone())]);
+        match existing.get(key) {
+            None => {
+                if lines.last().map(|l| !l.ends_with('\n')).unwrap_or(false) {
+                    lines.push(newline.to_string());
+                }
+                lines.extend(yaml_lines(&entry, newline)?);
+            }
+            Some(current) if precedence == MetadataPrecedence::Preset && current != value => {
+                let name = match key {
+                    YamlValue::String(name) => name.clone(),
+                    other => serde_yaml::to_string(other)
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_default(),
+                };
+                match key_block(&lines, &name) {
+                    Some((start, end)) => {
+                        lines.splice(start..end, yaml_lines(&entry, newline)?);
+                    }
+                    // 键名写法特殊（锚点、合并键等）无法定位时，整体重写
+                    None => return rewrite_front_matter(&existing, metadata, body, newline),
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    let yaml = lines.concat();
+    // 局部改写后应仍是合法 YAML，否则退回整体重写
+    if parse_mapping(&yaml).is_err() {
+        return rewrite_front_matter(&existing, metadata, body, newline);
+    }
+    Ok(format!("{}{}{}{}", opener, yaml, closer, body))
+}
+
+fn rewrite_front_matter(
+    existing: &YamlMapping,
+    metadata: &YamlMapping,
+    body: &str,
+    newline: &str,
+) -> Result<String, String> {
+    log::warn!("Front matter could not be edited in place, rewriting it");
+    let mut merged = existing.clone();
+    for (key, value) in metadata {
+        merged.insert(key.clone(), value.clone());
+    }
+    let yaml = yaml_lines(&merged, newline)?.concat();
+    Ok(format!("---{nl}{}---{nl}{}", yaml, body, nl = newline))
+}
+
+fn parse_mapping(yaml: &str) -> Result<YamlMapping, String> {
+    let value: YamlValue = serde_yaml::from_str(yaml).map_err(|e| match e.location() {
+        // 行号换算为文档中的行号（front matter 从第 2 行开始）
+        Some(location) => format!(
+            "Invalid YAML front matter (line {} of the document): {}",
+            location.line() + 1,
+            e
+        ),
+        None => format!("Invalid YAML front matter: {}", e),
+    })?;
+    match value {
+        YamlValue::Mapping(mapping) => Ok(mapping),
+        YamlValue::Null => Ok(YamlMapping::new()),
+        _ => Err("Invalid YAML front matter: expected key/value pairs".to_string()),
+    }
+}
+
+fn yaml_lines(mapping: &YamlMapping, newline: &str) -> Result<Vec<String>, String> {
+    let yaml = serde_yaml::to_string(&YamlValue::Mapping(mapping.clone()))
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    Ok(yaml
+        .split_inclusive('\n')
+        .map(|line| match line.strip_suffix('\n') {
+            Some(text) => format!("{}{}", text, newline),
+            None => line.to_string(),
+        })
+        .collect())
+}
+
+/// 顶层字段所占的行范围：字段行及其后缩进的续行、`- ` 列表项
+fn key_block(lines: &[String], key: &str) -> Option<(usize, usize)> {
+    let start = lines
+        .iter()
+        .position(|line| top_level_key(line).as_deref() == Some(key))?;
+
+    let mut end = start + 1;
+    let mut idx = start + 1;
+    while idx < lines.len() {
+        let line = &lines[idx];
+        if line.trim().is_empty() {
+            idx += 1;
+            continue;
+        }
+        let continues =
+            line.starts_with([' ', '\t']) || (line.starts_with('-') && !line.starts_with("---"));
+        if !continues {
+            break;
+        }
+        idx += 1;
+        end = idx;
+    }
+    Some((start, end))
+}
+
+fn top_level_key(line: &str) -> Option<String> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with([' ', '\t', '#', '-']) {
+        return None;
+    }
+
+    for quote in ['"', '\''] {
+        if let Some(rest) = line.strip_prefix(quote) {
+            let end = rest.find(quote)?;
+            return rest[end + 1..]
+                .trim_start()
+                .starts_with(':')
+                .then(|| rest[..end].to_string());
+        }
+    }
+
+    let colon = line.char_indices().find_map(|(i, c)| {
+        let at_end = line[i + 1..]
+            .chars()
+            .next()
+            .map(char::is_whitespace)
+            .unwrap_or(true);
+        (c == ':' && at_end).then_some(i)
+    })?;
+    Some(line[..colon].trim_end().to_string())
+}
+
+/// 序列化为 `---` 包围的 YAML 块
+pub fn yaml_block(mapping: &YamlMapping) -> Result<String, String> {
+    let yaml = serde_yaml::to_string(&YamlValue::Mapping(mapping.clone()))
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    Ok(format!("---\n{}---", yaml))
+}
+
+fn toml_front_matter(content: &str) -> Result<Option<(YamlMapping, &str)>, String> {
+    let mut offset = 0;
+    let mut lines = content.split_inclusive('\n');
+    match lines.next() {
+        Some(first) if first.trim_end() == "+++" => offset += first.len(),
+        _ => return Ok(None),
+    }
+
+    let start = offset;
+    for line in lines {
+        if line.trim_end() == "+++" {
+            let table: toml::Table = toml::from_str(&content[start..offset])
+                .map_err(|e| format!("Invalid TOML front matter: {}", e))?;
+            let mapping = table
+                .into_iter()
+                .map(|(k, v)| (YamlValue::String(k), toml_to_yaml(v)))
+                .collect();
+            return Ok(Some((mapping, &content[offset + line.len()..])));
+        }
+        offset += line.len();
+    }
+    Ok(None)
+}
+
+/// Hugo 的 JSON front matter 以 `{` 开头，到对象结束为止
+fn json_front_matter(content: &str) -> Result<Option<(YamlMapping, &str)>, String> {
+    let mut stream = serde_json::Deserializer::from_str(content).into_iter::<JsonValue>();
+    let value = match stream.next() {
+        Some(Ok(JsonValue::Object(map))) => map,
+        // 不是合法 JSON 对象，说明开头的 `{` 属于正文
+        _ => return Ok(None),
+    };
+    let end = stream.byte_offset();
+
+    let rest = &content[end..];
+    let line_end = rest.find('\n').unwrap_or(rest.len());
+    if !rest[..line_end].trim().is_empty() {
+        return Ok(None);
+    }
+
+    let yaml = serde_yaml::to_value(JsonValue::Object(value))
+        .map_err(|e| format!("Failed to convert JSON front matter: {}", e))?;
+    match yaml {
+        YamlValue::Mapping(mapping) => Ok(Some((mapping, &rest[line_end..]))),
+        _ => Ok(None),
+    }
+}
+
+fn toml_to_yaml(value: toml::Value) -> YamlValue {
+    match value {
+        toml::Value::String(s) => YamlValue::String(s),
+        toml::Value::Integer(i) => YamlValue::Number(i.into()),
+        toml::Value::Float(f) => YamlValue::Number(f.into()),
+        toml::Value::Boolean(b) => YamlValue::Bool(b),
+        // 日期保持原文，pandoc 的 date 字段本就是字符串
+        toml::Value::Datetime(dt) => YamlValue::String(dt.to_string()),
+        toml::Value::Array(items) => {
+            YamlValue::Sequence(items.into_iter().map(toml_to_yaml).collect())
+        }
+        toml::Value::Table(table) => YamlValue::Mapping(
+            table
+                .into_iter()
+                .map(|(k, v)| (YamlValue::String(k), toml_to_yaml(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 未启用 preserve_order，TOML 与 JSON 的键都按字母顺序输出
+    #[test]
+    fn toml_front_matter_becomes_yaml() {
+        let content =
+            "+++\ntitle = \"标题\"\ndate = 2024-01-02\ntags = [\"a\", \"b\"]\n+++\n\n正文\n";
+        assert_eq!(
+            normalize_front_matter(content).unwrap(),
+            "---\ndate: 2024-01-02\ntags:\n- a\n- b\ntitle: 标题\n---\n\n正文\n"
+        );
+    }
+
+    #[test]
+    fn json_front_matter_becomes_yaml() {
+        let content = "\u{feff}{\n  \"title\": \"标题\",\n  \"draft\": false\n}\n正文 {x}\n";
+        assert_eq!(
+            normalize_front_matter(content).unwrap(),
+            "---\ndraft: false\ntitle: 标题\n---\n\n正文 {x}\n"
+        );
+    }
+
+    #[test]
+    fn other_content_is_unchanged() {
+        for content in [
+            "---\ntitle: a\n---\n正文\n",
+            "{.class} 开头的正文\n",
+            "{\"a\": 1} 同一行还有正文\n",
+            "+++\n没有结束行\n",
+        ] {
+            assert_eq!(normalize_front_matter(content).unwrap(), content);
+        }
+        assert!(normalize_front_matter("+++\ntitle = \n+++\n").is_err());
+    }
+
+    fn metadata(yaml: &str) -> YamlMapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn merge_edits_only_changed_fields() {
+        let content = "---\n# 注释保留\ntitle: '旧标题'\nauthor:\n  - 张三\nlang: zh\n---\n正文\n";
+        let merged = merge_front_matter(
+            content,
+            &metadata("title: 新标题\nlang: zh\nfontsize: 12pt"),
+            MetadataPrecedence::Preset,
+        )
+        .unwrap();
+        assert_eq!(
+            merged,
+            "---\n# 注释保留\ntitle: 新标题\nauthor:\n  - 张三\nlang: zh\nfontsize: 12pt\n---\n正文\n"
+        );
+    }
+
+    #[test]
+    fn document_precedence_keeps_existing_fields() {
+        let content = "---\ntitle: 原标题\n---\n正文\n";
+        let merged = merge_front_matter(
+            content,
+            &metadata("title: 预设\nlang: zh"),
+            MetadataPrecedence::Document,
+        )
+        .unwrap();
+        assert_eq!(merged, "---\ntitle: 原标题\nlang: zh\n---\n正文\n");
+    }
+
+    #[test]
+    fn merge_adds_front_matter_and_keeps_crlf() {
+        assert_eq!(
+            merge_front_matter(
+                "\r\n正文\r\n",
+                &metadata("lang: zh"),
+                MetadataPrecedence::Preset
+            )
+            .unwrap(),
+            "---\r\nlang: zh\r\n---\r\n\r\n正文\r\n"
+        );
+        assert_eq!(
+            merge_front_matter(
+                "---\r\ntitle: a\r\n---\r\n正文\r\n",
+                &metadata("title: b"),
+                MetadataPrecedence::Preset
+            )
+            .unwrap(),
+            "---\r\ntitle: b\r\n---\r\n正文\r\n"
+        );
+    }
+
+    #[test]
+    fn invalid_front_matter_is_an_error() {
+        let err = merge_front_matter(
+            "---\ntitle: [unclosed\n---\n正文\n",
+            &metadata("lang: zh"),
+            MetadataPrecedence::Preset,
+        )
+        .unwrap_err();
+        assert!(err.starts_with("Invalid YAML front matter"), "{}", err);
+    }
+
+    #[test]
+    fn front_matter_length() {
+        assert_eq!(front_matter_len("---\na: 1\n...\nbody"), 13);
+        assert_eq!(front_matter_len("---\r\na: 1\r\n---\r\nbody"), 16);
+        assert_eq!(front_matter_len("---\na: 1\n"), 0);
+        assert_eq!(front_matter_len("正文\n---\n"), 0);
+    }
+}
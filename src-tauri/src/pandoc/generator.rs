@@ -0,0 +1,822 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::docx::DocxPackage;
+use super::inspect::PAPER_SIZES;
+use super::styles::escape_xml;
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// 中文字号与磅值对照
+const NAMED_SIZES: [(&str, f32); 16] = [
+    ("初号", 42.0),
+    ("小初", 36.0),
+    ("一号", 26.0),
+    ("小一", 24.0),
+    ("二号", 22.0),
+    ("小二", 18.0),
+    ("三号", 16.0),
+    ("小三", 15.0),
+    ("四号", 14.0),
+    ("小四", 12.0),
+    ("五号", 10.5),
+    ("小五", 9.0),
+    ("六号", 7.5),
+    ("小六", 6.5),
+    ("七号", 5.5),
+    ("八号", 5.0),
+];
+
+/// 声明式的模板样式描述，可写成 JSON 或 YAML
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleSpec {
+    pub page: PageSpec,
+    /// 正文：Normal 的字体、字号、行距，Body Text 的首行缩进与段距
+    pub body: TextSpec,
+    /// 依次对应 Heading 1–9，未给出的级别使用默认设置
+    pub headings: Vec<TextSpec>,
+    pub title: TextSpec,
+    pub caption: TextSpec,
+    pub table_caption: TextSpec,
+    pub image_caption: TextSpec,
+    pub code: TextSpec,
+    pub footnote: TextSpec,
+    pub table: TableSpec,
+}
+
+/// 文字与段落格式，未设置的项继承上级样式
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextSpec {
+    /// 中文字体，例如 宋体
+    pub font: Option<String>,
+    /// 西文字体，例如 Times New Roman
+    pub latin_font: Option<String>,
+    /// 磅值或中文字号，例如 12 或 "小四"
+    pub size: Option<FontSize>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    /// 十六进制颜色，例如 "1F3864"
+    pub color: Option<String>,
+    pub align: Option<Alignment>,
+    pub line_spacing: Option<LineSpacing>,
+    /// 首行缩进（字符数）
+    pub first_line_indent: Option<f32>,
+    /// 左缩进（字符数）
+    pub left_indent: Option<f32>,
+    /// 段前、段后间距（磅）
+    pub space_before: Option<f32>,
+    pub space_after: Option<f32>,
+}
+
+impl TextSpec {
+    /// 未设置的项取 `fallback` 中的值
+    fn or(&self, fallback: &TextSpec) -> TextSpec {
+        TextSpec {
+            font: self.font.clone().or_else(|| fallback.font.clone()),
+            latin_font: self
+                .latin_font
+                .clone()
+                .or_else(|| fallback.latin_font.clone()),
+            size: self.size.clone().or_else(|| fallback.size.clone()),
+            bold: self.bold.or(fallback.bold),
+            italic: self.italic.or(fallback.italic),
+            color: self.color.clone().or_else(|| fallback.color.clone()),
+            align: self.align.or(fallback.align),
+            line_spacing: self
+                .line_spacing
+                .clone()
+                .or_else(|| fallback.line_spacing.clone()),
+            first_line_indent: self.first_line_indent.or(fallback.first_line_indent),
+            left_indent: self.left_indent.or(fallback.left_indent),
+            space_before: self.space_before.or(fallback.space_before),
+            space_after: self.space_after.or(fallback.space_after),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FontSize {
+    Points(f32),
+    /// 中文字号，或带 pt 后缀的磅值
+    Named(String),
+}
+
+impl FontSize {
+    pub fn points(&self) -> Result<f32, String> {
+        match self {
+            FontSize::Points(pt) if *pt > 0.0 => Ok(*pt),
+            FontSize::Points(pt) => Err(format!("Invalid font size: {}", pt)),
+            FontSize::Named(name) => {
+                let name = name.trim();
+                NAMED_SIZES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, pt)| *pt)
+                    .or_else(|| name.trim_end_matches("pt").trim().parse::<f32>().ok())
+                    .filter(|pt| *pt > 0.0)
+                    .ok_or_else(|| format!("Invalid font size: {}", name))
+            }
+        }
+    }
+}
+
+/// 行距：数字为倍数，"20pt" 为固定值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LineSpacing {
+    Multiple(f32),
+    Text(String),
+}
+
+impl LineSpacing {
+    /// 返回 (w:line, w:lineRule)
+    pub(crate) fn attrs(&self) -> Result<(i64, &'static str), String> {
+        let text = match self {
+            LineSpacing::Multiple(multiple) => {
+                return Ok(((multiple * 240.0).round() as i64, "auto"))
+            }
+            LineSpacing::Text(text) => text.trim(),
+        };
+        if let Some(pt) = text.strip_suffix("pt") {
+            let pt: f32 = pt
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid line spacing: {}", text))?;
+            return Ok(((pt * 20.0).round() as i64, "exact"));
+        }
+        let multiple: f32 = text
+            .parse()
+            .map_err(|_| format!("Invalid line spacing: {}", text))?;
+        Ok(((multiple * 240.0).round() as i64, "auto"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl Alignment {
+    pub(crate) fn value(self) -> &'static str {
+        match self {
+            Alignment::Left => "left",
+            Alignment::Center => "center",
+            Alignment::Right => "right",
+            Alignment::Justify => "both",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageSpec {
+    /// 纸张名称，例如 A4、Letter；给出宽高时以宽高为准
+    pub size: Option<String>,
+    pub width_mm: Option<f32>,
+    pub height_mm: Option<f32>,
+    pub landscape: bool,
+    pub margins: MarginSpec,
+}
+
+/// 页边距（毫米）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarginSpec {
+    pub top: Option<f32>,
+    pub bottom: Option<f32>,
+    pub left: Option<f32>,
+    pub right: Option<f32>,
+    pub header: Option<f32>,
+    pub footer: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableSpec {
+    pub borders: TableBorders,
+    /// 表头行加粗，默认开启
+    pub header_bold: Option<bool>,
+    /// 表格内文字格式
+    pub text: TextSpec,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TableBorders {
+    /// 三线表：上下粗线，表头下细线
+    #[default]
+    ThreeLine,
+    Grid,
+    None,
+}
+
+/// 读取 JSON 或 YAML 格式的样式描述（YAML 解析器同样接受 JSON）
+pub fn load_style_spec(path: &Path) -> Result<StyleSpec, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read style spec: {}", e))?;
+    serde_yaml::from_str(&content).map_err(|e| format!("Invalid style spec: {}", e))
+}
+
+/// 按样式描述生成参考文档，包含 pandoc 会用到的全部样式
+pub fn build_reference_doc(spec: &StyleSpec) -> Result<Vec<u8>, String> {
+    let mut package = DocxPackage::default();
+    package.set_part("[Content_Types].xml", CONTENT_TYPES_XML);
+    package.set_part("_rels/.rels", ROOT_RELS_XML);
+    package.set_part("word/_rels/document.xml.rels", DOCUMENT_RELS_XML);
+    package.set_part("word/document.xml", document_xml(&spec.page)?);
+    package.set_part("word/styles.xml", styles_xml(spec)?);
+    package.set_part("word/settings.xml", SETTINGS_XML);
+    package.to_bytes()
+}
+
+fn styles_xml(spec: &StyleSpec) -> Result<String, String> {
+    let body = spec.body.or(&TextSpec {
+        font: Some("宋体".to_string()),
+        latin_font: Some("Times New Roman".to_string()),
+        size: Some(FontSize::Points(12.0)),
+        align: Some(Alignment::Justify),
+        line_spacing: Some(LineSpacing::Multiple(1.5)),
+        first_line_indent: Some(2.0),
+        ..Default::default()
+    });
+    // Normal 只承载字体、字号与行距，缩进与段距放在 Body Text，表格与列表不受影响
+    let normal = TextSpec {
+        first_line_indent: None,
+        left_indent: None,
+        space_before: None,
+        space_after: None,
+        ..body.clone()
+    };
+    let body_text = TextSpec {
+        first_line_indent: body.first_line_indent,
+        left_indent: body.left_indent,
+        space_before: body.space_before,
+        space_after: body.space_after,
+        ..Default::default()
+    };
+    let no_indent = TextSpec {
+        first_line_indent: Some(0.0),
+        ..Default::default()
+    };
+    let centered = TextSpec {
+        align: Some(Alignment::Center),
+        first_line_indent: Some(0.0),
+        ..Default::default()
+    };
+
+    let mut styles = vec![
+        paragraph("Normal", "Normal", None, &normal, false, None)?,
+        paragraph(
+            "BodyText",
+            "Body Text",
+            Some("Normal"),
+            &body_text,
+            false,
+            None,
+        )?,
+        paragraph(
+            "FirstParagraph",
+            "First Paragraph",
+            Some("BodyText"),
+            &TextSpec::default(),
+            false,
+            None,
+        )?,
+        paragraph(
+            "Compact",
+            "Compact",
+            Some("BodyText"),
+            &TextSpec {
+                space_before: Some(0.0),
+                space_after: Some(0.0),
+                ..no_indent.clone()
+            },
+            false,
+            None,
+        )?,
+        paragraph(
+            "Title",
+            "Title",
+            Some("Normal"),
+            &spec.title.or(&TextSpec {
+                size: Some(FontSize::Points(22.0)),
+                bold: Some(true),
+                space_before: Some(24.0),
+                space_after: Some(12.0),
+                ..centered.clone()
+            }),
+            true,
+            None,
+        )?,
+        paragraph(
+            "Subtitle",
+            "Subtitle",
+            Some("Title"),
+            &TextSpec {
+                size: Some(FontSize::Points(16.0)),
+                bold: Some(false),
+                ..Default::default()
+            },
+            true,
+            None,
+        )?,
+        paragraph("Author", "Author", Some("Normal"), &centered, true, None)?,
+        paragraph("Date", "Date", Some("Normal"), &centered, true, None)?,
+        paragraph(
+            "Abstract",
+            "Abstract",
+            Some("BodyText"),
+            &TextSpec::default(),
+            false,
+            None,
+        )?,
+    ];
+
+    for level in 0..9u8 {
+        let default = heading_default(level);
+        let heading = spec
+            .headings
+            .get(level as usize)
+            .map(|h| h.or(&default))
+            .unwrap_or(default);
+        styles.push(paragraph(
+            &format!("Heading{}", level + 1),
+            &format!("heading {}", level + 1),
+            Some("Normal"),
+            &heading,
+            true,
+            Some(level),
+        )?);
+    }
+
+    let caption = spec.caption.or(&TextSpec {
+        size: Some(FontSize::Points(10.5)),
+        space_before: Some(6.0),
+        space_after: Some(6.0),
+        ..centered.clone()
+    });
+    let code = spec.code.or(&TextSpec {
+        latin_font: Some("Consolas".to_string()),
+        size: Some(FontSize::Points(10.5)),
+        align: Some(Alignment::Left),
+        line_spacing: Some(LineSpacing::Multiple(1.0)),
+        ..no_indent.clone()
+    });
+    let footnote = spec.footnote.or(&TextSpec {
+        size: Some(FontSize::Points(9.0)),
+        align: Some(Alignment::Left),
+        line_spacing: Some(LineSpacing::Multiple(1.0)),
+        ..no_indent.clone()
+    });
+
+    styles.extend([
+        paragraph(
+            "BlockText",
+            "Block Text",
+            Some("BodyText"),
+            &TextSpec {
+                left_indent: Some(2.0),
+                ..Default::default()
+            },
+            false,
+            None,
+        )?,
+        paragraph(
+            "SourceCode",
+            "Source Code",
+            Some("Normal"),
+            &code,
+            false,
+            None,
+        )?,
+        paragraph(
+            "FootnoteText",
+            "footnote text",
+            Some("Normal"),
+            &footnote,
+            false,
+            None,
+        )?,
+        paragraph(
+            "Bibliography",
+            "Bibliography",
+            Some("Normal"),
+            &no_indent,
+            false,
+            None,
+        )?,
+        paragraph("Caption", "caption", Some("Normal"), &caption, false, None)?,
+        // 表格标题在表格上方，需与表格同页
+        paragraph(
+            "TableCaption",
+            "Table Caption",
+            Some("Caption"),
+            &spec.table_caption,
+            true,
+            None,
+        )?,
+        paragraph(
+            "ImageCaption",
+            "Image Caption",
+            Some("Caption"),
+            &spec.image_caption,
+            false,
+            None,
+        )?,
+        paragraph("Figure", "Figure", Some("Normal"), &centered, true, None)?,
+        character(
+            "VerbatimChar",
+            "Verbatim Char",
+            &TextSpec {
+                font: code.font.clone(),
+                latin_font: code.latin_font.clone(),
+                ..Default::default()
+            },
+            "",
+        )?,
+        character(
+            "Hyperlink",
+            "Hyperlink",
+            &TextSpec {
+                color: Some("0563C1".to_string()),
+                ..Default::default()
+            },
+            "<w:u w:val=\"single\"/>",
+        )?,
+        character(
+            "FootnoteReference",
+            "footnote reference",
+            &TextSpec::default(),
+            "<w:vertAlign w:val=\"superscript\"/>",
+        )?,
+        table_style(&spec.table)?,
+    ]);
+
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="{w}"><w:docDefaults><w:rPrDefault>{rpr}</w:rPrDefault><w:pPrDefault/></w:docDefaults>{styles}</w:styles>"#,
+        w = W_NS,
+        rpr = run_properties(&normal, "")?,
+        styles = styles.concat()
+    ))
+}
+
+fn heading_default(level: u8) -> TextSpec {
+    let (size, before, after) = match level {
+        0 => (16.0, 24.0, 12.0),
+        1 => (14.0, 12.0, 6.0),
+        _ => (12.0, 6.0, 6.0),
+    };
+    TextSpec {
+        font: Some("黑体".to_string()),
+        size: Some(FontSize::Points(size)),
+        bold: Some(true),
+        align: Some(Alignment::Left),
+        first_line_indent: Some(0.0),
+        space_before: Some(before),
+        space_after: Some(after),
+        ..Default::default()
+    }
+}
+
+fn paragraph(
+    id: &str,
+    name: &str,
+    based_on: Option<&str>,
+    text: &TextSpec,
+    keep_next: bool,
+    outline_level: Option<u8>,
+) -> Result<String, String> {
+    let mut xml = format!(
+        r#"<w:style w:type="paragraph"{} w:styleId="{}"><w:name w:val="{}"/>"#,
+        if id == "Normal" {
+            r#" w:default="1""#
+        } else {
+            ""
+        },
+        id,
+        name
+    );
+    if let Some(parent) = based_on {
+        xml.push_str(&format!(r#"<w:basedOn w:val="{}"/>"#, parent));
+    }
+    xml.push_str("<w:qFormat/>");
+    xml.push_str(&paragraph_properties(text, keep_next, outline_level)?);
+    xml.push_str(&run_properties(text, "")?);
+    xml.push_str("</w:style>");
+    Ok(xml)
+}
+
+fn character(id: &str, name: &str, text: &TextSpec, extra: &str) -> Result<String, String> {
+    Ok(format!(
+        r#"<w:style w:type="character" w:styleId="{}"><w:name w:val="{}"/>{}</w:style>"#,
+        id,
+        name,
+        run_properties(text, extra)?
+    ))
+}
+
+/// 段落属性，子元素须按 OOXML 规定的顺序写出
+fn paragraph_properties(
+    text: &TextSpec,
+    keep_next: bool,
+    outline_level: Option<u8>,
+) -> Result<String, String> {
+    let mut xml = String::new();
+    if keep_next {
+        xml.push_str("<w:keepNext/><w:keepLines/>");
+    }
+
+    let mut spacing = String::new();
+    if let Some(before) = text.space_before {
+        spacing.push_str(&format!(r#" w:before="{}""#, twips(before)));
+    }
+    if let Some(after) = text.space_after {
+        spacing.push_str(&format!(r#" w:after="{}""#, twips(after)));
+    }
+    if let Some(line) = &text.line_spacing {
+        let (value, rule) = line.attrs()?;
+        spacing.push_str(&format!(r#" w:line="{}" w:lineRule="{}""#, value, rule));
+    }
+    if !spacing.is_empty() {
+        xml.push_str(&format!("<w:spacing{}/>", spacing));
+    }
+
+    let mut indent = String::new();
+    if let Some(chars) = text.left_indent {
+        indent.push_str(&format!(
+            r#" w:leftChars="{}" w:left="0""#,
+            (chars * 100.0).round()
+        ));
+    }
+    if let Some(chars) = text.first_line_indent {
+        indent.push_str(&format!(
+            r#" w:firstLineChars="{}" w:firstLine="0""#,
+            (chars * 100.0).round()
+        ));
+    }
+    if !indent.is_empty() {
+        xml.push_str(&format!("<w:ind{}/>", indent));
+    }
+
+    if let Some(align) = text.align {
+        xml.push_str(&format!(r#"<w:jc w:val="{}"/>"#, align.value()));
+    }
+    if let Some(level) = outline_level {
+        xml.push_str(&format!(r#"<w:outlineLvl w:val="{}"/>"#, level));
+    }
+
+    Ok(if xml.is_empty() {
+        String::new()
+    } else {
+        format!("<w:pPr>{}</w:pPr>", xml)
+    })
+}
+
+/// 字符属性；`extra` 为额外的子元素（下划线、上标），按顺序位于字号之后
+fn run_properties(text: &TextSpec, extra: &str) -> Result<String, String> {
+    let mut xml = String::new();
+
+    let mut fonts = String::new();
+    if let Some(latin) = &text.latin_font {
+        let latin = escape_xml(latin);
+        fonts.push_str(&format!(
+            r#" w:ascii="{0}" w:hAnsi="{0}" w:cs="{0}""#,
+            latin
+        ));
+    }
+    if let Some(east_asia) = &text.font {
+        fonts.push_str(&format!(r#" w:eastAsia="{}""#, escape_xml(east_asia)));
+    }
+    if !fonts.is_empty() {
+        xml.push_str(&format!("<w:rFonts{}/>", fonts));
+    }
+
+    match text.bold {
+        Some(true) => xml.push_str("<w:b/><w:bCs/>"),
+        Some(false) => xml.push_str(r#"<w:b w:val="0"/><w:bCs w:val="0"/>"#),
+        None => {}
+    }
+    match text.italic {
+        Some(true) => xml.push_str("<w:i/><w:iCs/>"),
+        Some(false) => xml.push_str(r#"<w:i w:val="0"/><w:iCs w:val="0"/>"#),
+        None => {}
+    }
+    if let Some(color) = &text.color {
+        xml.push_str(&format!(r#"<w:color w:val="{}"/>"#, hex_color(color)?));
+    }
+    if let Some(size) = &text.size {
+        let half_points = (size.points()? * 2.0).round();
+        xml.push_str(&format!(
+            r#"<w:sz w:val="{0}"/><w:szCs w:val="{0}"/>"#,
+            half_points
+        ));
+    }
+    xml.push_str(extra);
+
+    Ok(if xml.is_empty() {
+        String::new()
+    } else {
+        format!("<w:rPr>{}</w:rPr>", xml)
+    })
+}
+
+fn table_style(table: &TableSpec) -> Result<String, String> {
+    let border = |side: &str, size: u32| {
+        format!(
+            r#"<w:{} w:val="single" w:sz="{}" w:space="0" w:color="auto"/>"#,
+            side, size
+        )
+    };
+    // 边框粗细单位为 1/8 磅
+    let (borders, header_border) = match table.borders {
+        TableBorders::ThreeLine => (
+            format!("{}{}", border("top", 12), border("bottom", 12)),
+            border("bottom", 6),
+        ),
+        TableBorders::Grid => (
+            ["top", "left", "bottom", "right", "insideH", "insideV"]
+                .iter()
+                .map(|side| border(side, 4))
+                .collect(),
+            String::new(),
+        ),
+        TableBorders::None => (String::new(), String::new()),
+    };
+
+    let text = table.text.or(&TextSpec {
+        align: Some(Alignment::Center),
+        first_line_indent: Some(0.0),
+        line_spacing: Some(LineSpacing::Multiple(1.0)),
+        ..Default::default()
+    });
+    let mut header = String::new();
+    if table.header_bold.unwrap_or(true) {
+        header.push_str("<w:rPr><w:b/><w:bCs/></w:rPr>");
+    }
+    if !header_border.is_empty() {
+        header.push_str(&format!(
+            "<w:tcPr><w:tcBorders>{}</w:tcBorders></w:tcPr>",
+            header_border
+        ));
+    }
+    let first_row = if header.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#"<w:tblStylePr w:type="firstRow">{}</w:tblStylePr>"#,
+            header
+        )
+    };
+
+    Ok(format!(
+        r#"<w:style w:type="table" w:styleId="Table"><w:name w:val="Table"/><w:basedOn w:val="TableNormal"/>{ppr}{rpr}<w:tblPr><w:jc w:val="center"/>{borders}<w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr>{first_row}</w:style><w:style w:type="table" w:default="1" w:styleId="TableNormal"><w:name w:val="Normal Table"/><w:tblPr><w:tblInd w:w="0" w:type="dxa"/><w:tblCellMar><w:top w:w="0" w:type="dxa"/><w:left w:w="108" w:type="dxa"/><w:bottom w:w="0" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>"#,
+        ppr = paragraph_properties(&text, false, None)?,
+        rpr = run_properties(&text, "")?,
+        borders = if borders.is_empty() {
+            String::new()
+        } else {
+            format!("<w:tblBorders>{}</w:tblBorders>", borders)
+        },
+        first_row = first_row
+    ))
+}
+
+fn document_xml(page: &PageSpec) -> Result<String, String> {
+    let (mut width, mut height) = match (page.width_mm, page.height_mm) {
+        (Some(w), Some(h)) => (mm_to_twips(w), mm_to_twips(h)),
+        _ => {
+            let name = page.size.as_deref().unwrap_or("A4");
+            PAPER_SIZES
+                .iter()
+                .find(|(n, _, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, w, h)| (*w as i64, *h as i64))
+                .ok_or_else(|| format!("Unknown paper size: {}", name))?
+        }
+    };
+    if page.landscape && width < height {
+        std::mem::swap(&mut width, &mut height);
+    }
+
+    let margins = &page.margins;
+    let margin = |value: Option<f32>, default: f32| mm_to_twips(value.unwrap_or(default));
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="{w}" xmlns:r="{r}"><w:body><w:p/><w:sectPr><w:pgSz w:w="{width}" w:h="{height}"{orient}/><w:pgMar w:top="{top}" w:right="{right}" w:bottom="{bottom}" w:left="{left}" w:header="{header}" w:footer="{footer}" w:gutter="0"/><w:cols w:space="425"/><w:docGrid w:type="lines" w:linePitch="312"/></w:sectPr></w:body></w:document>"#,
+        w = W_NS,
+        r = R_NS,
+        width = width,
+        height = height,
+        orient = if page.landscape {
+            r#" w:orient="landscape""#
+        } else {
+            ""
+        },
+        top = margin(margins.top, 25.4),
+        bottom = margin(margins.bottom, 25.4),
+        left = margin(margins.left, 31.8),
+        right = margin(margins.right, 31.8),
+        header = margin(margins.header, 15.0),
+        footer = margin(margins.footer, 17.5),
+    ))
+}
+
+/// 校验并规范化十六进制颜色，去掉 `#` 并转为大写
+pub(crate) fn hex_color(color: &str) -> Result<String, String> {
+    let color = color.trim_start_matches('#');
+    if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color: {}", color));
+    }
+    Ok(color.to_uppercase())
+}
+
+pub(crate) fn twips(points: f32) -> i64 {
+    (points * 20.0).round() as i64
+}
+
+pub(crate) fn mm_to_twips(mm: f32) -> i64 {
+    (mm / 25.4 * 1440.0).round() as i64
+}
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/settings.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml"/></Types>"#;
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" Target="settings.xml"/></Relationships>"#;
+
+const SETTINGS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:defaultTabStop w:val="420"/><w:drawingGridVerticalSpacing w:val="156"/><w:characterSpacingControl w:val="compressPunctuation"/><w:compat><w:spaceForUL/><w:balanceSingleByteDoubleByteWidth/><w:doNotLeaveBackslashAlone/><w:ulTrailSpace/><w:doNotExpandShiftReturn/><w:adjustLineHeightInTable/><w:useFELayout/><w:compatSetting w:name="compatibilityMode" w:uri="http://schemas.microsoft.com/office/word" w:val="15"/></w:compat></w:settings>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pandoc::inspect::inspect_docx;
+
+    #[test]
+    fn font_sizes_and_line_spacing() {
+        assert_eq!(FontSize::Named("小四".to_string()).points().unwrap(), 12.0);
+        assert_eq!(
+            FontSize::Named("10.5pt".to_string()).points().unwrap(),
+            10.5
+        );
+        assert!(FontSize::Named("特大".to_string()).points().is_err());
+        assert!(FontSize::Points(0.0).points().is_err());
+
+        assert_eq!(LineSpacing::Multiple(1.5).attrs().unwrap(), (360, "auto"));
+        assert_eq!(
+            LineSpacing::Text("20pt".to_string()).attrs().unwrap(),
+            (400, "exact")
+        );
+        assert!(LineSpacing::Text("double".to_string()).attrs().is_err());
+    }
+
+    #[test]
+    fn default_spec_has_all_pandoc_styles() {
+        let report = inspect_docx(&build_reference_doc(&StyleSpec::default()).unwrap()).unwrap();
+        assert!(
+            report.missing_pandoc_styles.is_empty(),
+            "{:?}",
+            report.missing_pandoc_styles
+        );
+        let normal = report
+            .paragraph_styles
+            .iter()
+            .find(|s| s.name == "Normal")
+            .unwrap();
+        assert_eq!(normal.fonts.east_asia.as_deref(), Some("宋体"));
+        assert_eq!(normal.size_pt, Some(12.0));
+    }
+
+    #[test]
+    fn yaml_spec_sets_body_and_page() {
+        let spec: StyleSpec = serde_yaml::from_str(
+            "page:\n  size: A4\n  landscape: true\nbody:\n  font: 仿宋\n  size: 五号\n",
+        )
+        .unwrap();
+        let report = inspect_docx(&build_reference_doc(&spec).unwrap()).unwrap();
+        let normal = report
+            .paragraph_styles
+            .iter()
+            .find(|s| s.name == "Normal")
+            .unwrap();
+        assert_eq!(normal.fonts.east_asia.as_deref(), Some("仿宋"));
+        assert_eq!(normal.size_pt, Some(10.5));
+
+        let page = report.page.unwrap();
+        assert!(page.landscape);
+        assert!(page.width_mm > page.height_mm);
+        assert_eq!(page.paper.as_deref(), Some("A4"));
+    }
+}
@@ -0,0 +1,181 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::Path;
+
+use super::input::make_unique_name;
+use super::reader::{pandoc_to_markdown, title_author_front_matter};
+
+/// 页面框架类元素，转换前整体移除
+const STRIPPED_ELEMENTS: [&str; 9] = [
+    "script", "style", "noscript", "template", "iframe", "nav", "footer", "aside", "form",
+];
+
+/// 将 HTML（含"网页另存为"得到的 `Page_files/` 目录）转为 Markdown
+///
+/// 先移除脚本与导航等页面框架，可选只保留 `<article>`/`<main>` 正文，
+/// 内嵌的 data URI 图片写入 `assets/`，其余图片路径保持相对 HTML 所在目录，
+/// 交给后续的图片复制流程处理。
+///
+/// `with_metadata` 为 false 时不输出 `<title>` 等 front matter（EPUB 章节由包元数据统一提供）。
+///
+/// 返回 (Markdown 文本, 写入的图片路径)
+pub fn html_to_markdown(
+    pandoc_exe: Option<&Path>,
+    html_path: &Path,
+    assets_dir: &Path,
+    main_content_only: bool,
+    with_metadata: bool,
+) -> Result<(String, Vec<String>), String> {
+    let bytes = fs::read(html_path).map_err(|e| format!("Failed to read HTML: {}", e))?;
+    let raw = decode_html(&bytes);
+
+    let title = capture_text(&raw, r"(?is)<title[^>]*>(.*?)</title>")?;
+    let author = meta_content(&raw, "author")?;
+
+    let mut html = strip_elements(&raw)?;
+    if main_content_only {
+        if let Some(main) = extract_main_content(&html) {
+            html = format!("<html><body>{}</body></html>", main);
+        }
+    }
+
+    let stem = html_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "page".to_string());
+    let (html, images) = extract_data_uri_images(&html, assets_dir, &stem)?;
+
+    // 清理后的 HTML 放在 session 目录中，转换时仍以原文件目录解析相对路径
+    let session_dir = assets_dir.parent().unwrap_or(assets_dir);
+    let cleaned_path = session_dir.join(format!("{}.cleaned.html", stem));
+    fs::write(&cleaned_path, html).map_err(|e| format!("Failed to write cleaned HTML: {}", e))?;
+
+    let markdown = pandoc_to_markdown(pandoc_exe, &cleaned_path, "html", &[], html_path.parent())?;
+
+    if !with_metadata {
+        return Ok((markdown, images));
+    }
+
+    let authors: Vec<String> = author.into_iter().collect();
+    let markdown = match title_author_front_matter(title.as_deref(), &authors)? {
+        Some(front_matter) => format!("{}\n\n{}", front_matter, markdown),
+        None => markdown,
+    };
+
+    Ok((markdown, images))
+}
+
+/// 按 `<meta charset>` 声明解码，国内网页常见 GBK/GB2312
+fn decode_html(bytes: &[u8]) -> String {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(2048)]).to_ascii_lowercase();
+    let label = Regex::new(r#"charset\s*=\s*["']?([a-z0-9_-]+)"#)
+        .ok()
+        .and_then(|re| re.captures(&head).map(|c| c[1].to_string()));
+
+    match label.and_then(|l| encoding_rs::Encoding::for_label(l.as_bytes())) {
+        Some(encoding) => encoding.decode(bytes).0.into_owned(),
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn strip_elements(html: &str) -> Result<String, String> {
+    let comment_regex =
+        Regex::new(r"(?s)<!--.*?-->").map_err(|e| format!("Failed to compile regex: {}", e))?;
+    let mut cleaned = comment_regex.replace_all(html, "").into_owned();
+
+    for tag in STRIPPED_ELEMENTS {
+        let pattern = format!(
+            r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>|<{tag}\b[^>]*/>",
+            tag = tag
+        );
+        let regex = Regex::new(&pattern).map_err(|e| format!("Failed to compile regex: {}", e))?;
+        cleaned = regex.replace_all(&cleaned, "").into_owned();
+    }
+    Ok(cleaned)
+}
+
+/// 优先 `<article>`，其次 `<main>`；多篇 article 时保留第一篇开头到最后一篇结尾
+fn extract_main_content(html: &str) -> Option<String> {
+    // 仅做 ASCII 小写，字节偏移与原文一致
+    let lower = html.to_ascii_lowercase();
+    for tag in ["article", "main"] {
+        let open = [format!("<{} ", tag), format!("<{}>", tag)]
+            .iter()
+            .filter_map(|needle| lower.find(needle.as_str()))
+            .min();
+        let close_tag = format!("</{}>", tag);
+        if let (Some(start), Some(end)) = (open, lower.rfind(&close_tag)) {
+            if end > start {
+                return Some(html[start..end + close_tag.len()].to_string());
+            }
+        }
+    }
+    None
+}
+
+fn extract_data_uri_images(
+    html: &str,
+    assets_dir: &Path,
+    stem: &str,
+) -> Result<(String, Vec<String>), String> {
+    let regex = Regex::new(
+        r#"(?i)src\s*=\s*["']data:image/(?P<kind>[a-z0-9.+-]+);base64,(?P<data>[^"']+)["']"#,
+    )
+    .map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    let mut images = Vec::new();
+    let mut counter = 0;
+    let rewritten = regex.replace_all(html, |caps: &Captures| {
+        let ext = match caps["kind"].to_lowercase().as_str() {
+            "jpeg" => "jpg".to_string(),
+            "svg+xml" => "svg".to_string(),
+            other => other.to_string(),
+        };
+        let compact: String = caps["data"]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        match BASE64.decode(compact) {
+            Ok(bytes) => {
+                counter += 1;
+                let name =
+                    make_unique_name(&format!("{}-image{}.{}", stem, counter, ext), assets_dir);
+                let target = assets_dir.join(&name);
+                if let Err(err) = fs::write(&target, bytes) {
+                    log::error!("Failed to write embedded image: {}", err);
+                    return caps[0].to_string();
+                }
+                images.push(target.to_string_lossy().to_string());
+                format!("src=\"assets/{}\"", name)
+            }
+            Err(err) => {
+                log::error!("Failed to decode embedded image: {}", err);
+                caps[0].to_string()
+            }
+        }
+    });
+
+    Ok((rewritten.into_owned(), images))
+}
+
+fn capture_text(html: &str, pattern: &str) -> Result<Option<String>, String> {
+    let regex = Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e))?;
+    Ok(regex
+        .captures(html)
+        .map(|c| c[1].split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty()))
+}
+
+fn meta_content(html: &str, name: &str) -> Result<Option<String>, String> {
+    let pattern = format!(
+        r#"(?is)<meta\b[^>]*\bname\s*=\s*["']{}["'][^>]*>"#,
+        regex::escape(name)
+    );
+    let tag_regex = Regex::new(&pattern).map_err(|e| format!("Failed to compile regex: {}", e))?;
+    match tag_regex.find(html) {
+        Some(tag) => capture_text(tag.as_str(), r#"(?is)\bcontent\s*=\s*["']([^"']*)["']"#),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,132 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::path::Path;
+
+/// 目录或压缩包根目录下的忽略文件，语法与 .gitignore 的常用子集一致
+pub const IGNORE_FILE_NAME: &str = ".formatsmanignore";
+
+/// 无论是否配置都跳过的目录
+const BUILTIN_IGNORED_DIRS: [&str; 3] = [".git", "node_modules", "__MACOSX"];
+
+struct IgnoreRule {
+    negated: bool,
+    dir_only: bool,
+}
+
+/// 按 .gitignore 规则匹配：后出现的规则优先，`!` 取反，结尾 `/` 只匹配目录
+pub struct IgnoreRules {
+    set: GlobSet,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    pub fn load(root: &Path) -> Self {
+        let content = fs::read_to_string(root.join(IGNORE_FILE_NAME)).unwrap_or_default();
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        let mut rules = Vec::new();
+
+        for raw in content.lines() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, pattern),
+            };
+
+            // 不含 `/` 的规则匹配任意层级，含 `/` 的规则相对根目录
+            let anchored = pattern.trim_start_matches('/');
+            let glob = if pattern.contains('/') {
+                anchored.to_string()
+            } else {
+                format!("**/{}", anchored)
+            };
+
+            // 与 .gitignore 一致，`*` 不跨越 `/`，只有 `**` 匹配多层目录
+            match GlobBuilder::new(&glob).literal_separator(true).build() {
+                Ok(g) => {
+                    builder.add(g);
+                    rules.push(IgnoreRule { negated, dir_only });
+                }
+                Err(err) => log::warn!("Invalid ignore pattern '{}': {}", line, err),
+            }
+        }
+
+        let set = builder.build().unwrap_or_else(|err| {
+            log::warn!("Failed to build ignore rules: {}", err);
+            GlobSet::empty()
+        });
+        // 构建失败时规则表也要清空，保持与 GlobSet 下标一致
+        if set.is_empty() {
+            rules.clear();
+        }
+
+        Self { set, rules }
+    }
+
+    /// `rel` 为相对根目录的路径
+    pub fn is_ignored(&self, rel: &Path, is_dir: bool) -> bool {
+        if is_dir {
+            if let Some(name) = rel.file_name().and_then(|n| n.to_str()) {
+                if BUILTIN_IGNORED_DIRS.contains(&name) {
+                    return true;
+                }
+            }
+        }
+
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        self.set
+            .matches(&rel)
+            .into_iter()
+            .filter(|idx| is_dir || !self.rules[*idx].dir_only)
+            .max()
+            .map(|idx| !self.rules[idx].negated)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(rules: &IgnoreRules, path: &str) -> bool {
+        rules.is_ignored(Path::new(path), false)
+    }
+
+    #[test]
+    fn star_does_not_cross_directories() {
+        let rules = IgnoreRules::parse("drafts/*.md\n");
+        assert!(ignored(&rules, "drafts/a.md"));
+        assert!(!ignored(&rules, "drafts/old/a.md"));
+        assert!(!ignored(&rules, "notes/drafts/a.md"));
+    }
+
+    #[test]
+    fn double_star_and_unanchored_patterns_match_any_depth() {
+        let rules = IgnoreRules::parse("drafts/**/*.md\n*.tmp\n");
+        assert!(ignored(&rules, "drafts/old/a.md"));
+        assert!(ignored(&rules, "a.tmp"));
+        assert!(ignored(&rules, "chapters/one/a.tmp"));
+        assert!(!ignored(&rules, "chapters/a.md"));
+    }
+
+    #[test]
+    fn later_negation_wins_and_trailing_slash_matches_dirs_only() {
+        let rules = IgnoreRules::parse("# comment\n*.md\n!keep.md\nbuild/\n");
+        assert!(ignored(&rules, "a.md"));
+        assert!(!ignored(&rules, "keep.md"));
+        assert!(rules.is_ignored(Path::new("build"), true));
+        assert!(!ignored(&rules, "build"));
+        assert!(rules.is_ignored(Path::new("docs/node_modules"), true));
+    }
+}
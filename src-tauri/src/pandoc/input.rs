@@ -6,7 +6,23 @@ use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Manager};
 
-use super::downloader::extract_archive;
+use super::book::{find_manifest, is_manifest_name, read_manifest};
+use super::chat::normalize_chat_markdown;
+use super::cjk::{typeset_cjk, CjkTypesetting};
+use super::config::get_pandoc_executable_path;
+use super::downloader::{extract_archive, extract_zip};
+use super::epub::read_epub_package;
+use super::frontmatter::normalize_front_matter;
+use super::html::html_to_markdown;
+use super::ignore::IgnoreRules;
+use super::latex::{is_latex_main, latex_to_markdown};
+use super::notebook::notebook_to_markdown;
+use super::reader::{packaged_to_markdown, title_author_front_matter};
+use super::shortcodes::translate_site_syntax;
+use super::tables::{expand_table_includes, find_fence_close, parse_fence_open, INCLUDE_PATTERN};
+
+/// `!include` 嵌套的最大深度
+const MAX_INCLUDE_DEPTH: usize = 16;
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "source_type", rename_all = "snake_case")]
@@ -15,6 +31,8 @@ pub enum InputSource {
         path: String,
         original_name: Option<String>,
         selected_markdown: Option<String>,
+        /// 按顺序合并的多个 Markdown 文件（相对压缩包根目录）
+        markdown_order: Option<Vec<String>>,
     },
     Text {
         content: String,
@@ -22,6 +40,24 @@ pub enum InputSource {
     },
 }
 
+/// 输入预处理的可选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct InputOptions {
+    /// Jupyter notebook 是否保留代码单元格
+    pub include_notebook_code: bool,
+    /// HTML 输入只保留 `<article>`/`<main>` 正文
+    pub html_main_content: bool,
+    /// 粘贴的文本按 AI 对话工具的输出习惯规范化（公式定界符、加粗、引用角标、列表）
+    pub normalize_chat: bool,
+    /// 修正紧贴中文或标点、pandoc 无法识别的强调
+    pub cjk_emphasis: bool,
+    /// 统一中文语境下的全角/半角标点与全角字母数字
+    pub cjk_punctuation: bool,
+    /// 中文与英文、数字之间加空格
+    pub cjk_spacing: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct PreparedInput {
     pub markdown_path: String,
@@ -29,13 +65,17 @@ pub struct PreparedInput {
     pub image_count: usize,
     pub copied_images: Vec<String>,
     pub markdown_files: Vec<String>,
+    pub assembled_files: Vec<String>,
     pub source_name: Option<String>,
     pub source_dir: Option<String>,
+    /// LaTeX 工程引用的 .bib（已复制到 session 目录），转换时交给 citeproc
+    pub bibliography: Vec<String>,
 }
 
 pub async fn prepare_input(
     app_handle: &AppHandle,
     source: InputSource,
+    options: InputOptions,
 ) -> Result<PreparedInput, String> {
     let session_dir = build_session_dir(app_handle)?;
     fs::create_dir_all(&session_dir).map_err(|e| format!("Failed to create session dir: {}", e))?;
@@ -43,11 +83,16 @@ pub async fn prepare_input(
     let assets_dir = session_dir.join("assets");
     fs::create_dir_all(&assets_dir).map_err(|e| format!("Failed to create assets dir: {}", e))?;
 
+    // 非 Markdown 来源（HTML 等）需要先由 pandoc 读成 Markdown
+    let pandoc_exe = get_pandoc_executable_path(app_handle).ok();
+    let mut loader = MarkdownLoader::new(&assets_dir, &options, pandoc_exe.as_deref());
+
     match source {
         InputSource::File {
             path,
             original_name,
             selected_markdown,
+            markdown_order,
         } => {
             let input_path = PathBuf::from(path.clone());
             if !input_path.exists() {
@@ -60,22 +105,25 @@ pub async fn prepare_input(
                     .map(|n| n.to_string_lossy().to_string())
             });
 
-            let (markdown_path, copied_images, markdown_files) = handle_file_input(
+            let handled = handle_file_input(
                 &input_path,
                 &session_dir,
-                &assets_dir,
+                &mut loader,
                 selected_markdown.as_deref(),
+                markdown_order.as_deref(),
             )
             .await?;
 
             Ok(PreparedInput {
-                markdown_path: markdown_path.to_string_lossy().to_string(),
+                markdown_path: handled.markdown_path.to_string_lossy().to_string(),
                 assets_dir: assets_dir.to_string_lossy().to_string(),
-                image_count: copied_images.len(),
-                copied_images,
-                markdown_files,
+                image_count: loader.copied_images.len(),
+                copied_images: loader.copied_images,
+                markdown_files: handled.markdown_files,
+                assembled_files: handled.assembled_files,
                 source_name: file_name,
                 source_dir: input_path.parent().map(|p| p.to_string_lossy().to_string()),
+                bibliography: loader.bibliography,
             })
         }
         InputSource::Text {
@@ -83,7 +131,15 @@ pub async fn prepare_input(
             suggested_name,
         } => {
             let markdown_path = session_dir.join("document.md");
-            let (copied_images, rewritten) = extract_and_copy_images(&content, None, &assets_dir)?;
+            let content = if options.normalize_chat {
+                normalize_chat_markdown(&content)?
+            } else {
+                content
+            };
+            let content = translate_site_syntax(&normalize_front_matter(&content)?, None)?;
+            let rewritten = loader.process(&content, None)?;
+            let rewritten = loader.typeset(&rewritten)?;
+            let copied_images = loader.copied_images;
             fs::write(&markdown_path, rewritten)
                 .map_err(|e| format!("Failed to write markdown: {}", e))?;
 
@@ -93,26 +149,43 @@ pub async fn prepare_input(
                 image_count: copied_images.len(),
                 copied_images,
                 markdown_files: vec![markdown_path.to_string_lossy().to_string()],
+                assembled_files: Vec::new(),
                 source_name: suggested_name,
                 source_dir: None,
+                bibliography: loader.bibliography,
             })
         }
     }
 }
 
+struct HandledInput {
+    markdown_path: PathBuf,
+    markdown_files: Vec<String>,
+    assembled_files: Vec<String>,
+}
+
 async fn handle_file_input(
     input_path: &Path,
     session_dir: &Path,
-    assets_dir: &Path,
+    loader: &mut MarkdownLoader<'_>,
     selected_markdown: Option<&str>,
-) -> Result<(PathBuf, Vec<String>, Vec<String>), String> {
+    markdown_order: Option<&[String]>,
+) -> Result<HandledInput, String> {
     let lower_name = input_path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("")
         .to_lowercase();
-
-    let (markdown_path, base_dir, markdown_files) = if is_archive(&lower_name) {
+    let target_md = session_dir.join("document.md");
+    // EPUB 的书名与作者取自包元数据，合并后统一放在开头
+    let mut front_matter = None;
+
+    let (root_dir, chapters, markdown_files) = if input_path.is_dir() {
+        // 直接读取文件夹，只复制被引用到的图片，不把整个目录搬进 session
+        let md_files = collect_markdown_files(input_path);
+        let chapters = select_chapters(input_path, &md_files, selected_markdown, markdown_order)?;
+        (input_path.to_path_buf(), chapters, md_files)
+    } else if is_archive(&lower_name) {
         let extract_dir = session_dir.join("extracted");
         fs::create_dir_all(&extract_dir)
             .map_err(|e| format!("Failed to create extract dir: {}", e))?;
@@ -121,41 +194,368 @@ async fn handle_file_input(
             .await
             .map_err(|e| format!("Failed to extract archive: {}", e))?;
 
-        let md_files = collect_markdown_files(&extract_dir, &extract_dir);
-        let selected_rel = selected_markdown
-            .and_then(|sel| md_files.iter().find(|p| p.as_str() == sel).cloned())
-            .or_else(|| md_files.get(0).cloned())
-            .ok_or_else(|| "No markdown file found in archive".to_string())?;
-
-        let md_file = extract_dir.join(&selected_rel);
-        let target_md = session_dir.join("document.md");
-        fs::copy(&md_file, &target_md).map_err(|e| format!("Failed to copy markdown: {}", e))?;
-
-        (
-            target_md,
-            md_file.parent().map(|p| p.to_path_buf()),
-            md_files,
-        )
+        let md_files = collect_markdown_files(&extract_dir);
+        let chapters = select_chapters(&extract_dir, &md_files, selected_markdown, markdown_order)?;
+        (extract_dir, chapters, md_files)
+    } else if lower_name.ends_with(".epub") {
+        let extract_dir = session_dir.join("extracted");
+        fs::create_dir_all(&extract_dir)
+            .map_err(|e| format!("Failed to create extract dir: {}", e))?;
+        extract_zip(input_path, &extract_dir)
+            .map_err(|e| format!("Failed to extract EPUB: {}", e))?;
+
+        // spine 即章节清单：默认整本合并，也可以指定单章或自定义顺序
+        let package = read_epub_package(&extract_dir)?;
+        let chapters = if selected_markdown.is_some() || markdown_order.is_some() {
+            select_chapters(
+                &extract_dir,
+                &package.chapters,
+                selected_markdown,
+                markdown_order,
+            )?
+        } else {
+            package
+                .chapters
+                .iter()
+                .map(|rel| extract_dir.join(rel))
+                .collect()
+        };
+        front_matter = title_author_front_matter(package.title.as_deref(), &package.authors)?;
+        loader.html_metadata = false;
+        (extract_dir, chapters, package.chapters)
+    } else if is_manifest_name(&lower_name) {
+        // 直接选择了 SUMMARY.md / _toc.yml，按清单合并同目录下的章节
+        let root = input_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let chapters = read_manifest(input_path)?;
+        let md_files = collect_markdown_files(&root);
+        (root, chapters, md_files)
     } else {
         // treat as a direct markdown/text file
-        let target_md = session_dir.join("document.md");
-        fs::copy(input_path, &target_md).map_err(|e| format!("Failed to copy markdown: {}", e))?;
+        let root = input_path.parent().unwrap_or(Path::new(".")).to_path_buf();
         (
-            target_md.clone(),
-            input_path.parent().map(|p| p.to_path_buf()),
+            root,
+            vec![input_path.to_path_buf()],
             vec![target_md.to_string_lossy().to_string()],
         )
     };
 
-    let content = fs::read_to_string(&markdown_path)
-        .map_err(|e| format!("Failed to read markdown: {}", e))?;
+    // 每个章节按自身所在目录解析图片与表格，再合并为一个 document.md
+    let mut parts = Vec::with_capacity(chapters.len() + 1);
+    parts.extend(front_matter);
+    for chapter in &chapters {
+        let content = loader.load_file(chapter)?;
+        parts.push(content.trim_end().to_string());
+    }
 
-    let (copied_images, rewritten) =
-        extract_and_copy_images(&content, base_dir.as_deref(), assets_dir)?;
-    fs::write(&markdown_path, rewritten)
+    let mut merged = loader.typeset(&parts.join("\n\n"))?;
+    merged.push('\n');
+    fs::write(&target_md, merged)
         .map_err(|e| format!("Failed to write processed markdown: {}", e))?;
 
-    Ok((markdown_path, copied_images, markdown_files))
+    let assembled_files = chapters
+        .iter()
+        .map(|p| {
+            p.strip_prefix(&root_dir)
+                .unwrap_or(p)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+
+    Ok(HandledInput {
+        markdown_path: target_md,
+        markdown_files,
+        assembled_files,
+    })
+}
+
+/// 决定压缩包中参与合并的章节：显式顺序 > 指定文件 > 书籍清单 > 第一个 Markdown
+fn select_chapters(
+    root: &Path,
+    md_files: &[String],
+    selected_markdown: Option<&str>,
+    markdown_order: Option<&[String]>,
+) -> Result<Vec<PathBuf>, String> {
+    if let Some(order) = markdown_order.filter(|o| !o.is_empty()) {
+        let mut chapters = Vec::with_capacity(order.len());
+        for rel in order {
+            if !md_files.iter().any(|p| p == rel) {
+                return Err(format!("Markdown file '{}' not found in input", rel));
+            }
+            chapters.push(root.join(rel));
+        }
+        return Ok(chapters);
+    }
+
+    if let Some(selected) = selected_markdown.and_then(|sel| md_files.iter().find(|p| *p == sel)) {
+        let path = root.join(selected);
+        let is_manifest = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(is_manifest_name)
+            .unwrap_or(false);
+        return if is_manifest {
+            read_manifest(&path)
+        } else {
+            Ok(vec![path])
+        };
+    }
+
+    if let Some(manifest) = find_manifest(root) {
+        return read_manifest(&manifest);
+    }
+
+    // 除 README 外没有其他文档时，按 LaTeX 工程处理（优先 main.tex，其次层级最浅者）
+    let only_latex = md_files.iter().all(|rel| {
+        let path = Path::new(rel);
+        source_kind(path) == Some(SourceKind::Latex) || is_readme(path)
+    });
+    if only_latex {
+        let latex_main = md_files
+            .iter()
+            .filter(|rel| source_kind(Path::new(rel)) == Some(SourceKind::Latex))
+            .min_by_key(|rel| {
+                let path = Path::new(rel.as_str());
+                let is_main = path.file_name().and_then(|n| n.to_str()) == Some("main.tex");
+                (!is_main, path.components().count())
+            });
+        if let Some(rel) = latex_main {
+            return Ok(vec![root.join(rel)]);
+        }
+    }
+
+    md_files
+        .first()
+        .map(|rel| vec![root.join(rel)])
+        .ok_or_else(|| "No markdown file found in input".to_string())
+}
+
+fn is_readme(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| s.eq_ignore_ascii_case("readme"))
+        .unwrap_or(false)
+}
+
+/// 读取 Markdown 来源并展开其中的表格、图片与 `!include` 引用，
+/// 收集所有复制到 assets 的图片
+struct MarkdownLoader<'a> {
+    assets_dir: &'a Path,
+    options: &'a InputOptions,
+    pandoc_exe: Option<&'a Path>,
+    /// HTML 章节是否输出自身的 title/author
+    html_metadata: bool,
+    include_stack: Vec<PathBuf>,
+    copied_images: Vec<String>,
+    bibliography: Vec<String>,
+}
+
+impl<'a> MarkdownLoader<'a> {
+    fn new(assets_dir: &'a Path, options: &'a InputOptions, pandoc_exe: Option<&'a Path>) -> Self {
+        Self {
+            assets_dir,
+            options,
+            pandoc_exe,
+            html_metadata: true,
+            include_stack: Vec::new(),
+            copied_images: Vec::new(),
+            bibliography: Vec::new(),
+        }
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<String, String> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.include_stack.contains(&canonical) {
+            return Err(format!("Circular include detected: {}", path.display()));
+        }
+        if self.include_stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(format!("Include nesting too deep at: {}", path.display()));
+        }
+
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "document".to_string());
+        let content = match source_kind(path) {
+            Some(SourceKind::Notebook) => {
+                let raw = read_text(path)?;
+                let (markdown, images) = notebook_to_markdown(
+                    &raw,
+                    self.assets_dir,
+                    &stem,
+                    self.options.include_notebook_code,
+                )?;
+                self.copied_images.extend(images);
+                markdown
+            }
+            Some(SourceKind::Html) => {
+                let (markdown, images) = html_to_markdown(
+                    self.pandoc_exe,
+                    path,
+                    self.assets_dir,
+                    self.options.html_main_content,
+                    self.html_metadata,
+                )?;
+                self.copied_images.extend(images);
+                markdown
+            }
+            Some(SourceKind::Odt) => {
+                // 解压到 session/media/<stem>/，图片以绝对路径引用，下面的图片处理会复制进 assets
+                let session_dir = self.assets_dir.parent().unwrap_or(self.assets_dir);
+                let media_root = session_dir.join("media");
+                let media_dir = media_root.join(make_unique_name(&stem, &media_root));
+                packaged_to_markdown(self.pandoc_exe, path, "odt", &media_dir)?
+            }
+            Some(SourceKind::Latex) => {
+                let document = latex_to_markdown(self.pandoc_exe, path)?;
+                for bib in &document.bibliographies {
+                    self.stage_bibliography(bib)?;
+                }
+                document.markdown
+            }
+            // Hugo/Hexo/VuePress 等静态站点的源文件
+            _ => {
+                let raw = normalize_front_matter(&read_text(path)?)?;
+                translate_site_syntax(&raw, Some(path))?
+            }
+        };
+
+        self.include_stack.push(canonical);
+        let result = self.process(&content, path.parent());
+        self.include_stack.pop();
+        result
+    }
+
+    /// 合并完成后的中文排版预处理；AI 对话的输出常有紧贴中文标点的加粗，规范化时一并修正强调
+    fn typeset(&self, content: &str) -> Result<String, String> {
+        typeset_cjk(
+            content,
+            CjkTypesetting {
+                emphasis: self.options.cjk_emphasis || self.options.normalize_chat,
+                punctuation: self.options.cjk_punctuation,
+                spacing: self.options.cjk_spacing,
+            },
+        )
+    }
+
+    /// .bib 复制到 session 目录，转换时 pandoc 的工作目录正是这里
+    fn stage_bibliography(&mut self, bib: &Path) -> Result<(), String> {
+        let session_dir = self.assets_dir.parent().unwrap_or(self.assets_dir);
+        let base_name = bib
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "references.bib".to_string());
+        let target = session_dir.join(make_unique_name(&base_name, session_dir));
+        fs::copy(bib, &target).map_err(|e| format!("Failed to copy bibliography: {}", e))?;
+        self.bibliography.push(target.to_string_lossy().to_string());
+        Ok(())
+    }
+
+    fn process(&mut self, content: &str, base_dir: Option<&Path>) -> Result<String, String> {
+        // 先处理本文件自己的表格与图片，被引用文件的内容已按其目录处理过，不会被重复改写
+        let content = expand_table_includes(content, base_dir)?;
+        let (images, rewritten) = extract_and_copy_images(&content, base_dir, self.assets_dir)?;
+        self.copied_images.extend(images);
+        self.expand_includes(&rewritten, base_dir)
+    }
+
+    /// 把 `!include chapter.md` 替换为目标文件内容（代码块中的指令不处理）
+    fn expand_includes(
+        &mut self,
+        content: &str,
+        base_dir: Option<&Path>,
+    ) -> Result<String, String> {
+        let include_regex =
+            Regex::new(INCLUDE_PATTERN).map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut output: Vec<String> = Vec::with_capacity(lines.len());
+        let mut i = 0;
+
+        while i < lines.len() {
+            if let Some((fence_char, fence_len, _)) = parse_fence_open(lines[i]) {
+                let end = find_fence_close(&lines, i + 1, fence_char, fence_len)
+                    .map(|idx| idx + 1)
+                    .unwrap_or(lines.len());
+                output.extend(lines[i..end].iter().map(|l| l.to_string()));
+                i = end;
+                continue;
+            }
+
+            let line = lines[i];
+            i += 1;
+
+            let Some(caps) = include_regex.captures(line.trim_end()) else {
+                output.push(line.to_string());
+                continue;
+            };
+            let target = caps
+                .name("quoted")
+                .or_else(|| caps.name("path"))
+                .map(|m| m.as_str())
+                .unwrap_or("");
+            if source_kind(Path::new(target)).is_none() {
+                output.push(line.to_string());
+                continue;
+            }
+
+            match resolve_image_path(target, base_dir).filter(|p| p.is_file()) {
+                Some(path) => {
+                    let included = self.load_file(&path)?;
+                    output.push(included.trim_end().to_string());
+                }
+                None => {
+                    log::error!("Included markdown not found: {}", target);
+                    output.push(line.to_string());
+                }
+            }
+        }
+
+        let mut result = output.join("\n");
+        if content.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+}
+
+/// 可作为章节读入的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceKind {
+    Markdown,
+    Notebook,
+    Html,
+    Latex,
+    Odt,
+}
+
+fn source_kind(path: &Path) -> Option<SourceKind> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "md" | "markdown" | "txt" => Some(SourceKind::Markdown),
+        "ipynb" => Some(SourceKind::Notebook),
+        "html" | "htm" | "xhtml" => Some(SourceKind::Html),
+        "tex" => Some(SourceKind::Latex),
+        "odt" => Some(SourceKind::Odt),
+        _ => None,
+    }
+}
+
+/// "网页另存为"生成的 `xxx_files/` 目录里的 HTML 只是页面附件，不作为候选文档
+fn in_companion_dir(rel: &Path) -> bool {
+    rel.parent()
+        .map(|parent| {
+            parent.components().any(|c| {
+                let name = c.as_os_str().to_string_lossy().to_lowercase();
+                name.ends_with("_files") || name.ends_with(".files")
+            })
+        })
+        .unwrap_or(false)
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read markdown {}: {}", path.display(), e))
 }
 
 fn is_archive(name: &str) -> bool {
@@ -165,28 +565,38 @@ fn is_archive(name: &str) -> bool {
         || name.ends_with(".7z")
 }
 
-fn collect_markdown_files(dir: &Path, base: &Path) -> Vec<String> {
+fn collect_markdown_files(base: &Path) -> Vec<String> {
+    let rules = IgnoreRules::load(base);
     let mut results = Vec::new();
+    walk_markdown_files(base, base, &rules, &mut results);
+    results.sort();
+    results
+}
+
+fn walk_markdown_files(dir: &Path, base: &Path, rules: &IgnoreRules, results: &mut Vec<String>) {
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
+            let rel = path.strip_prefix(base).unwrap_or(&path);
+            if rules.is_ignored(rel, path.is_dir()) {
+                continue;
+            }
+
             if path.is_file() {
-                if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                    let ext = ext.to_lowercase();
-                    if ext == "md" || ext == "markdown" || ext == "txt" {
-                        if let Ok(rel) = path.strip_prefix(base) {
-                            results.push(rel.to_string_lossy().to_string());
-                        }
-                    }
+                let candidate = match source_kind(&path) {
+                    // 被 \input 的章节文件不能单独转换，只列出主文件
+                    Some(SourceKind::Latex) => is_latex_main(&path),
+                    Some(_) => !in_companion_dir(rel),
+                    None => false,
+                };
+                if candidate {
+                    results.push(rel.to_string_lossy().to_string());
                 }
             } else if path.is_dir() {
-                let nested = collect_markdown_files(&path, base);
-                results.extend(nested);
+                walk_markdown_files(&path, base, rules, results);
             }
         }
     }
-    results.sort();
-    results
 }
 
 fn extract_and_copy_images(
@@ -202,7 +612,10 @@ fn extract_and_copy_images(
 
     let rewritten = img_regex
         .replace_all(content, |caps: &regex::Captures| {
-            let img_path_str = caps.name("path").map(|m| m.as_str().trim()).unwrap_or("");
+            let img_path_str = caps
+                .name("path")
+                .map(|m| image_target(m.as_str()))
+                .unwrap_or("");
 
             if img_path_str.starts_with("http://") || img_path_str.starts_with("https://") {
                 return caps.get(0).map(|m| m.as_str()).unwrap_or("").to_string();
@@ -244,6 +657,20 @@ fn extract_and_copy_images(
     Ok((copied, rewritten))
 }
 
+/// 去掉链接目标中的尖括号与标题：`<a b.png> "title"` -> `a b.png`
+fn image_target(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    if let Some(rest) = trimmed.strip_prefix('<') {
+        if let Some(end) = rest.find('>') {
+            return &rest[..end];
+        }
+    }
+    match trimmed.find(|c: char| c.is_whitespace()) {
+        Some(idx) if trimmed[idx..].trim_start().starts_with(['"', '\'', '(']) => &trimmed[..idx],
+        _ => trimmed,
+    }
+}
+
 fn resolve_image_path(img: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
     let candidate = Path::new(img);
     if candidate.is_absolute() {
@@ -255,12 +682,37 @@ fn resolve_image_path(img: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
         if joined.exists() {
             return Some(joined);
         }
+
+        // 网页另存为得到的路径常带 %20 等转义
+        let decoded = base.join(percent_decode(img));
+        if decoded.exists() {
+            return Some(decoded);
+        }
     }
 
     None
 }
 
-fn make_unique_name(base_name: &str, assets_dir: &Path) -> String {
+pub(crate) fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub(crate) fn make_unique_name(base_name: &str, assets_dir: &Path) -> String {
     let mut candidate = base_name.to_string();
     let mut counter = 1;
 
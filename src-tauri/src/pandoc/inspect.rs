@@ -0,0 +1,315 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use super::crypto::decrypt_template;
+use super::styles::{
+    docx_styles, read_docx_part, xml_attr, xml_element, DocxStyle, RunFonts, StyleKind,
+};
+
+/// pandoc 的 docx writer 会引用的样式（按名称匹配，不区分大小写）
+const PANDOC_STYLES: [(&str, StyleKind); 30] = [
+    ("Normal", StyleKind::Paragraph),
+    ("Body Text", StyleKind::Paragraph),
+    ("First Paragraph", StyleKind::Paragraph),
+    ("Compact", StyleKind::Paragraph),
+    ("Title", StyleKind::Paragraph),
+    ("Subtitle", StyleKind::Paragraph),
+    ("Author", StyleKind::Paragraph),
+    ("Date", StyleKind::Paragraph),
+    ("Abstract", StyleKind::Paragraph),
+    ("Heading 1", StyleKind::Paragraph),
+    ("Heading 2", StyleKind::Paragraph),
+    ("Heading 3", StyleKind::Paragraph),
+    ("Heading 4", StyleKind::Paragraph),
+    ("Heading 5", StyleKind::Paragraph),
+    ("Heading 6", StyleKind::Paragraph),
+    ("Heading 7", StyleKind::Paragraph),
+    ("Heading 8", StyleKind::Paragraph),
+    ("Heading 9", StyleKind::Paragraph),
+    ("Block Text", StyleKind::Paragraph),
+    ("Source Code", StyleKind::Paragraph),
+    ("Footnote Text", StyleKind::Paragraph),
+    ("Bibliography", StyleKind::Paragraph),
+    ("Caption", StyleKind::Paragraph),
+    ("Table Caption", StyleKind::Paragraph),
+    ("Image Caption", StyleKind::Paragraph),
+    ("Figure", StyleKind::Paragraph),
+    ("Verbatim Char", StyleKind::Character),
+    ("Hyperlink", StyleKind::Character),
+    ("Footnote Reference", StyleKind::Character),
+    ("Table", StyleKind::Table),
+];
+
+/// 常见纸张尺寸（twips，纵向）
+pub(crate) const PAPER_SIZES: [(&str, f32, f32); 7] = [
+    ("A3", 16838.0, 23811.0),
+    ("A4", 11906.0, 16838.0),
+    ("A5", 8391.0, 11906.0),
+    ("B5", 10319.0, 14571.0),
+    ("16K", 10433.0, 14742.0),
+    ("Letter", 12240.0, 15840.0),
+    ("Legal", 12240.0, 20160.0),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateReport {
+    pub paragraph_styles: Vec<DocxStyle>,
+    pub character_styles: Vec<DocxStyle>,
+    pub table_styles: Vec<DocxStyle>,
+    pub numbering_styles: Vec<DocxStyle>,
+    pub fonts: FontReport,
+    /// 最后一节（正文）的页面设置
+    pub page: Option<PageSetup>,
+    pub pandoc_styles: Vec<RequiredStyle>,
+    /// 缺失的 pandoc 样式名，为空表示模板完整
+    pub missing_pandoc_styles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FontReport {
+    /// docDefaults 中的默认字体与字号
+    pub default_fonts: RunFonts,
+    pub default_size_pt: Option<f32>,
+    pub theme: ThemeFonts,
+    /// fontTable.xml 中声明的字体
+    pub declared: Vec<String>,
+    /// 默认设置与各样式实际用到的字体，主题字体已换算为字体名
+    pub used: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ThemeFonts {
+    pub major_latin: Option<String>,
+    pub major_east_asia: Option<String>,
+    pub minor_latin: Option<String>,
+    pub minor_east_asia: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageSetup {
+    pub width_mm: f32,
+    pub height_mm: f32,
+    pub landscape: bool,
+    /// 识别出的纸张名称，例如 A4
+    pub paper: Option<String>,
+    pub margins: PageMargins,
+}
+
+/// 页边距（毫米）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageMargins {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+    pub header: f32,
+    pub footer: f32,
+    pub gutter: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequiredStyle {
+    pub name: String,
+    pub kind: StyleKind,
+    pub present: bool,
+}
+
+/// 检查磁盘上的模板文件，给出 key 时先在内存中解密
+pub fn inspect_file(path: &Path, key: Option<String>) -> Result<TemplateReport, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read template: {}", e))?;
+    match key.filter(|k| !k.is_empty()) {
+        Some(key) => inspect_docx(&decrypt_template(&bytes, &key)?),
+        None => inspect_docx(&bytes),
+    }
+}
+
+/// 检查内存中的 .docx：样式、字体、页面设置以及 pandoc 所需样式是否齐全
+pub fn inspect_docx(bytes: &[u8]) -> Result<TemplateReport, String> {
+    let styles = docx_styles(bytes)?;
+    let styles_xml = read_docx_part(bytes, "word/styles.xml")?.unwrap_or_default();
+    let theme = read_docx_part(bytes, "word/theme/theme1.xml")?
+        .map(|xml| parse_theme(&xml))
+        .transpose()?
+        .unwrap_or_default();
+    let declared = read_docx_part(bytes, "word/fontTable.xml")?
+        .map(|xml| font_table(&xml))
+        .unwrap_or_default();
+    let page = read_docx_part(bytes, "word/document.xml")?.and_then(|xml| page_setup(&xml));
+
+    // docDefaults 的 rPrDefault 决定未单独设置字体的样式
+    let defaults = styles_xml
+        .find("<w:rPrDefault")
+        .and_then(|start| {
+            let rest = &styles_xml[start..];
+            rest.find("</w:rPrDefault>").map(|end| &rest[..end])
+        })
+        .unwrap_or("");
+    let default_fonts = RunFonts::parse(defaults);
+    let default_size_pt = xml_element(defaults, "w:sz")
+        .and_then(|tag| xml_attr(tag, "w:val"))
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|half_points| half_points / 2.0);
+
+    let mut used = BTreeSet::new();
+    for fonts in std::iter::once(&default_fonts).chain(styles.iter().map(|s| &s.fonts)) {
+        for font in [&fonts.ascii, &fonts.east_asia, &fonts.h_ansi]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(name) = resolve_theme_font(font, &theme) {
+                used.insert(name);
+            }
+        }
+    }
+
+    let pandoc_styles: Vec<RequiredStyle> = PANDOC_STYLES
+        .iter()
+        .map(|&(name, kind)| RequiredStyle {
+            name: name.to_string(),
+            kind,
+            present: styles
+                .iter()
+                .any(|s| s.kind == kind && s.name.eq_ignore_ascii_case(name)),
+        })
+        .collect();
+    let missing_pandoc_styles = pandoc_styles
+        .iter()
+        .filter(|s| !s.present)
+        .map(|s| s.name.clone())
+        .collect();
+
+    let of_kind = |kind: StyleKind| -> Vec<DocxStyle> {
+        styles.iter().filter(|s| s.kind == kind).cloned().collect()
+    };
+    Ok(TemplateReport {
+        paragraph_styles: of_kind(StyleKind::Paragraph),
+        character_styles: of_kind(StyleKind::Character),
+        table_styles: of_kind(StyleKind::Table),
+        numbering_styles: of_kind(StyleKind::Numbering),
+        fonts: FontReport {
+            default_fonts,
+            default_size_pt,
+            theme,
+            declared,
+            used: used.into_iter().collect(),
+        },
+        page,
+        pandoc_styles,
+        missing_pandoc_styles,
+    })
+}
+
+/// `+minorEastAsia` 等主题字体引用换算为主题中的字体名
+fn resolve_theme_font(font: &str, theme: &ThemeFonts) -> Option<String> {
+    let Some(reference) = font.strip_prefix('+') else {
+        return Some(font.to_string());
+    };
+    let resolved = match reference {
+        "majorAscii" | "majorHAnsi" => &theme.major_latin,
+        "majorEastAsia" => &theme.major_east_asia,
+        "minorAscii" | "minorHAnsi" => &theme.minor_latin,
+        "minorEastAsia" => &theme.minor_east_asia,
+        _ => &None,
+    };
+    resolved.clone()
+}
+
+fn parse_theme(xml: &str) -> Result<ThemeFonts, String> {
+    // ea 为空时 Word 按 script 取中文字体
+    let hans = Regex::new(r#"<a:font\s+script="Hans"\s+typeface="([^"]*)""#)
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+    let section = |tag: &str| {
+        let start = xml.find(&format!("<a:{}>", tag))?;
+        let rest = &xml[start..];
+        Some(&rest[..rest.find(&format!("</a:{}>", tag)).unwrap_or(rest.len())])
+    };
+    let typeface = |section: &str, tag: &str| {
+        xml_element(section, tag)
+            .and_then(|t| xml_attr(t, "typeface"))
+            .filter(|f| !f.is_empty())
+    };
+    let fonts = |section: Option<&str>| match section {
+        Some(section) => (
+            typeface(section, "a:latin"),
+            typeface(section, "a:ea").or_else(|| {
+                hans.captures(section)
+                    .map(|c| c[1].to_string())
+                    .filter(|f| !f.is_empty())
+            }),
+        ),
+        None => (None, None),
+    };
+
+    let (major_latin, major_east_asia) = fonts(section("majorFont"));
+    let (minor_latin, minor_east_asia) = fonts(section("minorFont"));
+    Ok(ThemeFonts {
+        major_latin,
+        major_east_asia,
+        minor_latin,
+        minor_east_asia,
+    })
+}
+
+fn font_table(xml: &str) -> Vec<String> {
+    let mut fonts = Vec::new();
+    let mut rest = xml;
+    while let Some(tag) = xml_element(rest, "w:font") {
+        if let Some(name) = xml_attr(tag, "w:name") {
+            fonts.push(name);
+        }
+        let offset = rest.find(tag).unwrap_or(0) + tag.len();
+        rest = &rest[offset..];
+    }
+    fonts
+}
+
+fn page_setup(document: &str) -> Option<PageSetup> {
+    // 正文最后的 sectPr 描述最后一节，也是 pandoc 输出沿用的页面设置
+    let section = &document[document.rfind("<w:sectPr")?..];
+    let twips = |tag: &str, name: &str| {
+        xml_attr(tag, name)
+            .and_then(|v| v.parse::<f32>().ok())
+            .unwrap_or(0.0)
+    };
+
+    let size = xml_element(section, "w:pgSz")?;
+    let (width, height) = (twips(size, "w:w"), twips(size, "w:h"));
+    let landscape = xml_attr(size, "w:orient").as_deref() == Some("landscape") || width > height;
+    let (short, long) = if width < height {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    let paper = PAPER_SIZES
+        .iter()
+        .find(|(_, w, h)| (short - w).abs() <= 20.0 && (long - h).abs() <= 20.0)
+        .map(|(name, _, _)| name.to_string());
+
+    let margins = xml_element(section, "w:pgMar")
+        .map(|tag| PageMargins {
+            top: to_mm(twips(tag, "w:top")),
+            bottom: to_mm(twips(tag, "w:bottom")),
+            left: to_mm(twips(tag, "w:left")),
+            right: to_mm(twips(tag, "w:right")),
+            header: to_mm(twips(tag, "w:header")),
+            footer: to_mm(twips(tag, "w:footer")),
+            gutter: to_mm(twips(tag, "w:gutter")),
+        })
+        .unwrap_or_default();
+
+    Some(PageSetup {
+        width_mm: to_mm(width),
+        height_mm: to_mm(height),
+        landscape,
+        paper,
+        margins,
+    })
+}
+
+/// twips（1/1440 英寸）换算为毫米，保留一位小数
+fn to_mm(twips: f32) -> f32 {
+    (twips / 1440.0 * 25.4 * 10.0).round() / 10.0
+}
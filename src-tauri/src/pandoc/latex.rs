@@ -0,0 +1,165 @@
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::reader::pandoc_to_markdown;
+
+/// `\includegraphics` 可以省略扩展名，按 LaTeX 的查找顺序补全
+const GRAPHICS_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "pdf", "eps", "svg", "gif"];
+
+/// 转换 LaTeX 工程的主文件
+pub struct LatexDocument {
+    pub markdown: String,
+    /// 工程引用的 .bib 文件（绝对路径）
+    pub bibliographies: Vec<PathBuf>,
+}
+
+/// 含 `\documentclass`（非注释行）的 .tex 才是可独立编译的主文件
+pub fn is_latex_main(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| {
+            strip_comments(&content)
+                .lines()
+                .any(|line| line.trim_start().starts_with("\\documentclass"))
+        })
+        .unwrap_or(false)
+}
+
+/// 用 pandoc 读取主 .tex 文件：`\input`/`\include` 由 pandoc 以主文件目录为基准展开，
+/// 图片路径按 `\graphicspath` 与常见扩展名补全为实际存在的文件。
+pub fn latex_to_markdown(
+    pandoc_exe: Option<&Path>,
+    tex_path: &Path,
+) -> Result<LatexDocument, String> {
+    let base_dir = tex_path.parent().unwrap_or(Path::new("."));
+    let source =
+        fs::read_to_string(tex_path).map_err(|e| format!("Failed to read LaTeX file: {}", e))?;
+    let source = strip_comments(&source);
+
+    let graphics_dirs = graphics_paths(&source)?;
+    let bibliographies = bibliography_files(&source, base_dir)?;
+
+    // -s 保留 \title、\author、\date 等元数据为 YAML front matter
+    let markdown = pandoc_to_markdown(
+        pandoc_exe,
+        tex_path,
+        "latex",
+        &["-s".to_string()],
+        Some(base_dir),
+    )?;
+    let markdown = resolve_graphics(&markdown, base_dir, &graphics_dirs)?;
+
+    Ok(LatexDocument {
+        markdown,
+        bibliographies,
+    })
+}
+
+/// 去掉 `%` 注释（保留转义的 `\%`）
+fn strip_comments(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| {
+            let mut prev = '\0';
+            for (idx, ch) in line.char_indices() {
+                if ch == '%' && prev != '\\' {
+                    return &line[..idx];
+                }
+                prev = ch;
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `\graphicspath{{figures/}{img/}}`
+fn graphics_paths(source: &str) -> Result<Vec<String>, String> {
+    let regex = Regex::new(r"\\graphicspath\s*\{((?:\s*\{[^}]*\})*)\s*\}")
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+    let inner =
+        Regex::new(r"\{([^}]*)\}").map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    Ok(regex
+        .captures_iter(source)
+        .flat_map(|caps| {
+            inner
+                .captures_iter(&caps[1])
+                .map(|c| c[1].trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|dir| !dir.is_empty())
+        .collect())
+}
+
+/// `\bibliography{a,b}` 与 biblatex 的 `\addbibresource{a.bib}`
+fn bibliography_files(source: &str, base_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let regex = Regex::new(r"\\(?:bibliography|addbibresource)\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}")
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    let mut files = Vec::new();
+    for caps in regex.captures_iter(source) {
+        for name in caps[1].split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let file = if name.to_lowercase().ends_with(".bib") {
+                name.to_string()
+            } else {
+                format!("{}.bib", name)
+            };
+            let path = base_dir.join(&file);
+            if path.is_file() {
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            } else {
+                log::warn!("Bibliography file not found: {}", file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// 将 pandoc 输出中找不到的图片目标替换为 graphicspath/扩展名补全后的相对路径
+fn resolve_graphics(
+    markdown: &str,
+    base_dir: &Path,
+    graphics_dirs: &[String],
+) -> Result<String, String> {
+    let img_regex = Regex::new(r"!\[(?P<alt>[^\]]*)\]\((?P<path>[^)\s]+)(?P<rest>[^)]*)\)")
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    let rewritten = img_regex.replace_all(markdown, |caps: &Captures| {
+        let target = &caps["path"];
+        if target.contains("://") || base_dir.join(target).is_file() {
+            return caps[0].to_string();
+        }
+
+        let mut dirs = vec![String::new()];
+        dirs.extend(graphics_dirs.iter().cloned());
+        let found = dirs.iter().find_map(|dir| {
+            let stem = format!("{}{}", dir, target);
+            std::iter::once(stem.clone())
+                .chain(
+                    GRAPHICS_EXTENSIONS
+                        .iter()
+                        .map(|ext| format!("{}.{}", stem, ext)),
+                )
+                .find(|candidate| base_dir.join(candidate).is_file())
+        });
+
+        match found {
+            Some(path) => {
+                let lower = path.to_lowercase();
+                if lower.ends_with(".pdf") || lower.ends_with(".eps") {
+                    log::warn!("Figure {} is {}, Word may not display it", target, path);
+                }
+                format!("![{}]({}{})", &caps["alt"], path, &caps["rest"])
+            }
+            None => {
+                log::warn!("Figure not found for \\includegraphics{{{}}}", target);
+                caps[0].to_string()
+            }
+        }
+    });
+
+    Ok(rewritten.into_owned())
+}
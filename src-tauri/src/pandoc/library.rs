@@ -0,0 +1,415 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use super::docx::DocxPackage;
+use super::styles::xml_attr;
+
+const USER_TEMPLATE_PREFIX: &str = "user-";
+
+/// 文档关系中与正文内容无关、需要保留的部件类型
+const STRUCTURAL_RELATIONSHIPS: [&str; 13] = [
+    "styles",
+    "stylesWithEffects",
+    "numbering",
+    "settings",
+    "webSettings",
+    "fontTable",
+    "theme",
+    "header",
+    "footer",
+    "footnotes",
+    "endnotes",
+    "glossaryDocument",
+    "customXml",
+];
+
+/// 用户导入的模板
+///
+/// 存放在应用数据目录的 `templates/` 下，`<id>.docx` 为去掉正文的参考文档，`<id>.json` 为元数据。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 导入时的原始文件名
+    pub source_name: String,
+    /// 导入时间（毫秒时间戳）
+    pub imported_at: u64,
+}
+
+pub fn user_templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join("templates"))
+}
+
+/// 用户模板的 docx 路径；不是用户模板 ID 或文件不存在时返回 None
+pub fn user_template_path(app: &AppHandle, id: &str) -> Result<Option<PathBuf>, String> {
+    if !is_user_template_id(id) {
+        return Ok(None);
+    }
+    let path = user_templates_dir(app)?.join(format!("{}.docx", id));
+    Ok(path.exists().then_some(path))
+}
+
+/// 导入 .docx/.dotx：去掉正文内容，保留样式、页眉页脚、编号与页面设置
+pub fn import_template(
+    app: &AppHandle,
+    path: &Path,
+    name: Option<String>,
+) -> Result<UserTemplate, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    if ext != "docx" && ext != "dotx" {
+        return Err("Only .docx and .dotx templates can be imported".to_string());
+    }
+
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read template: {}", e))?;
+    let stripped = strip_template(&bytes)?;
+
+    let dir = user_templates_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create template directory: {}", e))?;
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_millis() as u64;
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "template".to_string());
+
+    let id = store_template_docx(&dir, millis, &stripped)?;
+    let template = UserTemplate {
+        id,
+        name: name
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .unwrap_or(stem),
+        description: String::new(),
+        source_name: path
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        imported_at: millis,
+    };
+
+    if let Err(e) = write_meta(&dir, &template) {
+        let _ = fs::remove_file(dir.join(format!("{}.docx", template.id)));
+        return Err(e);
+    }
+    Ok(template)
+}
+
+/// 以 `user-<毫秒>` 为 ID 写入参考文档；同一毫秒内的多次导入依次加 `-1`、`-2` 后缀
+///
+/// 文件以 create_new 创建，ID 已被占用时换下一个，并发导入也不会互相覆盖。
+fn store_template_docx(dir: &Path, millis: u64, bytes: &[u8]) -> Result<String, String> {
+    for attempt in 0..1000 {
+        let id = match attempt {
+            0 => format!("{}{}", USER_TEMPLATE_PREFIX, millis),
+            n => format!("{}{}-{}", USER_TEMPLATE_PREFIX, millis, n),
+        };
+        if dir.join(format!("{}.json", id)).exists() {
+            continue;
+        }
+        let mut file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(format!("{}.docx", id)))
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to store template: {}", e)),
+        };
+        if let Err(e) = file.write_all(bytes) {
+            let _ = fs::remove_file(dir.join(format!("{}.docx", id)));
+            return Err(format!("Failed to store template: {}", e));
+        }
+        return Ok(id);
+    }
+    Err("Failed to store template: no free template id".to_string())
+}
+
+pub fn rename_template(app: &AppHandle, id: &str, name: &str) -> Result<UserTemplate, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+    let dir = user_templates_dir(app)?;
+    let mut template = read_meta(&dir, id)?;
+    template.name = name.to_string();
+    write_meta(&dir, &template)?;
+    Ok(template)
+}
+
+pub fn delete_template(app: &AppHandle, id: &str) -> Result<(), String> {
+    let dir = user_templates_dir(app)?;
+    read_meta(&dir, id)?;
+    for ext in ["docx", "json"] {
+        let path = dir.join(format!("{}.{}", id, ext));
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to delete template: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 按导入时间排列的用户模板，缺少 docx 的条目会被跳过
+pub fn user_templates(app: &AppHandle) -> Result<Vec<UserTemplate>, String> {
+    let dir = user_templates_dir(app)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut templates = Vec::new();
+    let entries =
+        fs::read_dir(&dir).map_err(|e| format!("Failed to read template directory: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !dir.join(format!("{}.docx", id)).exists() {
+            continue;
+        }
+        match read_meta(&dir, id) {
+            Ok(template) => templates.push(template),
+            Err(e) => log::warn!("Skipping user template {}: {}", id, e),
+        }
+    }
+    templates.sort_by_key(|t| t.imported_at);
+    Ok(templates)
+}
+
+fn is_user_template_id(id: &str) -> bool {
+    id.strip_prefix(USER_TEMPLATE_PREFIX)
+        .map(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(false)
+}
+
+fn read_meta(dir: &Path, id: &str) -> Result<UserTemplate, String> {
+    if !is_user_template_id(id) {
+        return Err(format!("Invalid user template id: {}", id));
+    }
+    let path = dir.join(format!("{}.json", id));
+    if !path.exists() {
+        return Err(format!("User template '{}' not found", id));
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read template metadata: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid template metadata: {}", e))
+}
+
+fn write_meta(dir: &Path, template: &UserTemplate) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(template)
+        .map_err(|e| format!("Failed to serialize template metadata: {}", e))?;
+    fs::write(dir.join(format!("{}.json", template.id)), json)
+        .map_err(|e| format!("Failed to write template metadata: {}", e))
+}
+
+/// 去掉正文，只保留最后的节属性（页面设置及页眉页脚引用）
+///
+/// .dotx 的主部件类型改为文档类型，正文引用的图片、批注等部件一并删除。
+pub fn strip_template(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut package = DocxPackage::from_bytes(bytes)?;
+
+    let content_types = package.part("[Content_Types].xml").unwrap_or_default();
+    if content_types.contains("macroEnabled") {
+        return Err("Macro-enabled templates are not supported".to_string());
+    }
+    let mut content_types = content_types.replace(
+        "wordprocessingml.template.main+xml",
+        "wordprocessingml.document.main+xml",
+    );
+
+    let document = package
+        .part("word/document.xml")
+        .ok_or("Not a Word document: missing word/document.xml")?;
+    let document = strip_body(&document)?;
+
+    let rels_name = "word/_rels/document.xml.rels";
+    if let Some(rels) = package.part(rels_name) {
+        let relationship = Regex::new(r"<Relationship\b[^>]*?(?:/>|>\s*</Relationship>)")
+            .map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+        let mut removed = Vec::new();
+        let kept = relationship.replace_all(&rels, |caps: &regex::Captures| {
+            let tag = &caps[0];
+            let id = xml_attr(tag, "Id").unwrap_or_default();
+            let kind = xml_attr(tag, "Type").unwrap_or_default();
+            let kind = kind.rsplit('/').next().unwrap_or("");
+            let referenced = document.contains(&format!("\"{}\"", id));
+            if referenced || STRUCTURAL_RELATIONSHIPS.contains(&kind) {
+                return tag.to_string();
+            }
+            if xml_attr(tag, "TargetMode").as_deref() != Some("External") {
+                if let Some(target) = xml_attr(tag, "Target") {
+                    removed.push(resolve_target(&target));
+                }
+            }
+            String::new()
+        });
+        let kept = kept.into_owned();
+
+        for part in removed {
+            // 页眉页脚等其他部件仍引用的文件（例如页眉中的 logo）保留
+            let file_name = part.rsplit('/').next().unwrap_or(&part).to_string();
+            let shared = package.part_names().any(|name| {
+                name.ends_with(".rels")
+                    && name != rels_name
+                    && package
+                        .part(name)
+                        .map(|r| r.contains(&file_name))
+                        .unwrap_or(false)
+            });
+            if shared {
+                continue;
+            }
+            package.remove_part(&part);
+            package.remove_part(&rels_path(&part));
+            content_types = remove_override(&content_types, &part);
+        }
+        package.set_part(rels_name, kept);
+    }
+
+    package.set_part("word/document.xml", document);
+    package.set_part("[Content_Types].xml", content_types);
+    package.to_bytes()
+}
+
+fn strip_body(document: &str) -> Result<String, String> {
+    let start = document
+        .find("<w:body>")
+        .ok_or("Invalid document.xml: missing w:body")?
+        + "<w:body>".len();
+    let end = document
+        .rfind("</w:body>")
+        .ok_or("Invalid document.xml: missing w:body")?;
+    let body = &document[start..end];
+
+    // 正文级 sectPr 是 body 的最后一个子元素
+    let section = body
+        .rfind("<w:sectPr")
+        .map(|pos| body[pos..].trim_end())
+        .filter(|s| s.ends_with("</w:sectPr>") || s.ends_with("/>"))
+        .unwrap_or("");
+
+    Ok(format!(
+        "{}<w:p/>{}{}",
+        &document[..start],
+        section,
+        &document[end..]
+    ))
+}
+
+/// 关系目标相对于 word/ 目录，以 `/` 开头的是包内绝对路径
+fn resolve_target(target: &str) -> String {
+    match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => {
+            let mut parts: Vec<&str> = vec!["word"];
+            for segment in target.split('/') {
+                match segment {
+                    ".." => {
+                        parts.pop();
+                    }
+                    "." | "" => {}
+                    other => parts.push(other),
+                }
+            }
+            parts.join("/")
+        }
+    }
+}
+
+fn rels_path(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, name)) => format!("{}/_rels/{}.rels", dir, name),
+        None => format!("_rels/{}.rels", part),
+    }
+}
+
+fn remove_override(content_types: &str, part: &str) -> String {
+    let needle = format!("PartName=\"/{}\"", part);
+    let Some(pos) = content_types.find(&needle) else {
+        return content_types.to_string();
+    };
+    let start = content_types[..pos].rfind('<').unwrap_or(pos);
+    let end = content_types[pos..]
+        .find("/>")
+        .map(|e| pos + e + 2)
+        .unwrap_or(pos);
+    format!("{}{}", &content_types[..start], &content_types[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "formatsman-library-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn same_millisecond_imports_get_distinct_ids() {
+        let dir = scratch_dir("ids");
+        let ids: Vec<String> = (0..3u8)
+            .map(|n| store_template_docx(&dir, 1700000000000, &[n]).unwrap())
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "user-1700000000000",
+                "user-1700000000000-1",
+                "user-1700000000000-2"
+            ]
+        );
+        for (n, id) in ids.iter().enumerate() {
+            assert!(is_user_template_id(id));
+            assert_eq!(
+                fs::read(dir.join(format!("{}.docx", id))).unwrap(),
+                [n as u8]
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ids_with_leftover_metadata_are_skipped() {
+        let dir = scratch_dir("meta");
+        // 只剩元数据（.docx 已丢失）的 ID 也不能复用
+        fs::write(dir.join("user-5.json"), "{}").unwrap();
+        assert_eq!(store_template_docx(&dir, 5, b"x").unwrap(), "user-5-1");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn template_ids_are_validated() {
+        assert!(is_user_template_id("user-1700000000000-1"));
+        assert!(!is_user_template_id("user-"));
+        assert!(!is_user_template_id("user-../x"));
+        assert!(!is_user_template_id("plain"));
+    }
+}
@@ -0,0 +1,524 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
+use ring::{hmac, signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+const LICENSE_FILE: &str = "license.json";
+/// 授权中表示全部模板的通配项
+const ALL_TEMPLATES: &str = "*";
+/// 包装内容 key 时 HMAC 与 AEAD 附加数据的前缀
+const WRAP_CONTEXT: &str = "formatsman-license-key";
+const WRAP_NONCE_LEN: usize = 12;
+
+/// 签发授权用的 Ed25519 公钥（base64），构建时通过环境变量嵌入
+const LICENSE_PUBLIC_KEY: Option<&str> = option_env!("FORMATSMAN_LICENSE_PUBLIC_KEY");
+
+/// 授权文件：`payload` 为 base64 编码的 JSON，`signature` 为其 Ed25519 签名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedLicense {
+    payload: String,
+    signature: String,
+}
+
+/// 签名内容
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct License {
+    id: String,
+    #[serde(default)]
+    licensee: String,
+    /// RFC 3339 时间或 YYYY-MM-DD（当天结束时到期）；为空表示永久
+    #[serde(default)]
+    expires_at: Option<String>,
+    /// 授权的模板 ID，`*` 表示全部
+    templates: Vec<String>,
+    /// 用户密钥（base64），只用于解开本授权中包装的模板 key
+    key: String,
+    /// 模板 ID → 按用户包装的内容 key（base64：nonce | 密文+tag），见 `unwrap_content_key`
+    ///
+    /// 每个受保护模板只有一份加密文件、一个内容 key；授权中不出现明文 key，
+    /// 每份授权的包装结果都不同。没有列出的模板即使在 `templates` 范围内也无法解密。
+    #[serde(default)]
+    wrapped_keys: BTreeMap<String, String>,
+}
+
+/// 返回给前端的授权信息，不含模板 key
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseStatus {
+    pub id: String,
+    pub licensee: String,
+    pub expires_at: Option<String>,
+    pub expired: bool,
+    pub templates: Vec<String>,
+}
+
+/// 校验并保存授权文件；过期或签名无效的授权不会被安装
+pub fn install_license(app: &AppHandle, path: &Path) -> Result<LicenseStatus, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read license: {}", e))?;
+    let license = verify_license(&content, &embedded_public_key()?)?;
+    let status = license.status_at(Utc::now())?;
+    if status.expired {
+        return Err(expired_message(&status));
+    }
+
+    let target = license_path(app)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    fs::write(&target, content).map_err(|e| format!("Failed to save license: {}", e))?;
+    Ok(status)
+}
+
+pub fn remove_license(app: &AppHandle) -> Result<(), String> {
+    let path = license_path(app)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove license: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 当前授权的状态；未安装时为 None
+pub fn license_status(app: &AppHandle) -> Result<Option<LicenseStatus>, String> {
+    current_license(app)?
+        .map(|l| l.status_at(Utc::now()))
+        .transpose()
+}
+
+/// 判断能否使用受保护模板，并解开其内容 key
+///
+/// 每次都重新校验签名与有效期，磁盘上的授权文件被改动后立即失效。
+pub fn template_key(app: &AppHandle, template_id: &str) -> Result<String, String> {
+    current_license(app)?
+        .ok_or_else(|| "A member license is required for this template".to_string())?
+        .content_key(template_id, Utc::now())
+}
+
+/// 当前安装且签名有效的授权；未安装或无法校验时为 None
+///
+/// 只校验签名，不解开模板 key，供模板列表等频繁调用的地方判断授权范围。
+pub(crate) fn verified_license(app: &AppHandle) -> Option<License> {
+    current_license(app).ok().flatten()
+}
+
+impl License {
+    /// 授权在 `now` 有效、包含该模板且带有其 key 时为 true，不做解密
+    pub(crate) fn unlocks(&self, template_id: &str, now: DateTime<Utc>) -> bool {
+        self.status_at(now).is_ok_and(|status| !status.expired)
+            && self.entitles(template_id)
+            && self.wrapped_keys.contains_key(template_id)
+    }
+
+    fn entitles(&self, template_id: &str) -> bool {
+        self.templates
+            .iter()
+            .any(|t| t == ALL_TEMPLATES || t == template_id)
+    }
+
+    fn status_at(&self, now: DateTime<Utc>) -> Result<LicenseStatus, String> {
+        let expired = match &self.expires_at {
+            Some(expires_at) => parse_expiry(expires_at)? < now,
+            None => false,
+        };
+        Ok(LicenseStatus {
+            id: self.id.clone(),
+            licensee: self.licensee.clone(),
+            expires_at: self.expires_at.clone(),
+            expired,
+            templates: self.templates.clone(),
+        })
+    }
+
+    /// 授权在 `now` 有效且包含该模板时解开模板的内容 key
+    fn content_key(&self, template_id: &str, now: DateTime<Utc>) -> Result<String, String> {
+        let status = self.status_at(now)?;
+        if status.expired {
+            return Err(expired_message(&status));
+        }
+        if !self.entitles(template_id) {
+            return Err(format!(
+                "License does not include template '{}'",
+                template_id
+            ));
+        }
+        let wrapped = self
+            .wrapped_keys
+            .get(template_id)
+            .ok_or_else(|| format!("License has no key for template '{}'", template_id))?;
+        unwrap_content_key(&self.key, &self.id, template_id, wrapped)
+    }
+}
+
+/// 包装内容 key 用的 AES-256-GCM key 与附加数据，两者都绑定授权 ID 与模板 ID
+///
+/// key = HMAC-SHA256(用户密钥, context)，context = `formatsman-license-key\0授权ID\0模板ID`。
+/// 包装结果搬到其他授权或其他模板下都无法解开。
+fn wrapping_key(
+    user_key: &str,
+    license_id: &str,
+    template_id: &str,
+) -> Result<(Aes256Gcm, String), String> {
+    let user_key = STANDARD
+        .decode(user_key.trim())
+        .map_err(|e| format!("Invalid license key: {}", e))?;
+    let context = format!("{}\0{}\0{}", WRAP_CONTEXT, license_id, template_id);
+    let tag = hmac::sign(
+        &hmac::Key::new(hmac::HMAC_SHA256, &user_key),
+        context.as_bytes(),
+    );
+    let cipher = Aes256Gcm::new_from_slice(tag.as_ref())
+        .map_err(|e| format!("Invalid license key: {}", e))?;
+    Ok((cipher, context))
+}
+
+fn unwrap_content_key(
+    user_key: &str,
+    license_id: &str,
+    template_id: &str,
+    wrapped: &str,
+) -> Result<String, String> {
+    let data = STANDARD
+        .decode(wrapped.trim())
+        .map_err(|e| format!("Invalid key for template '{}': {}", template_id, e))?;
+    if data.len() <= WRAP_NONCE_LEN {
+        return Err(format!("Invalid key for template '{}'", template_id));
+    }
+    let (nonce, ciphertext) = data.split_at(WRAP_NONCE_LEN);
+    let (cipher, context) = wrapping_key(user_key, license_id, template_id)?;
+    let plain = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: context.as_bytes(),
+            },
+        )
+        .map_err(|_| format!("Failed to unlock template '{}'", template_id))?;
+    String::from_utf8(plain).map_err(|_| format!("Invalid key for template '{}'", template_id))
+}
+
+fn expired_message(status: &LicenseStatus) -> String {
+    format!(
+        "License expired on {}",
+        status.expires_at.clone().unwrap_or_default()
+    )
+}
+
+fn license_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join(LICENSE_FILE))
+}
+
+fn current_license(app: &AppHandle) -> Result<Option<License>, String> {
+    let path = license_path(app)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read license: {}", e))?;
+    verify_license(&content, &embedded_public_key()?).map(Some)
+}
+
+fn embedded_public_key() -> Result<Vec<u8>, String> {
+    let public_key =
+        LICENSE_PUBLIC_KEY.ok_or("License verification is not available in this build")?;
+    STANDARD
+        .decode(public_key.trim())
+        .map_err(|e| format!("Invalid license public key: {}", e))
+}
+
+/// 用给定公钥校验授权文件的签名并解析内容
+fn verify_license(content: &str, public_key: &[u8]) -> Result<License, String> {
+    let signed: SignedLicense =
+        serde_json::from_str(content).map_err(|e| format!("Invalid license file: {}", e))?;
+    let payload = STANDARD
+        .decode(signed.payload.trim())
+        .map_err(|e| format!("Invalid license payload: {}", e))?;
+    let signature_bytes = STANDARD
+        .decode(signed.signature.trim())
+        .map_err(|e| format!("Invalid license signature: {}", e))?;
+
+    signature::UnparsedPublicKey::new(&signature::ED25519, public_key)
+        .verify(&payload, &signature_bytes)
+        .map_err(|_| "License signature is invalid".to_string())?;
+
+    let license: License =
+        serde_json::from_slice(&payload).map_err(|e| format!("Invalid license payload: {}", e))?;
+    if let Some(expires_at) = &license.expires_at {
+        parse_expiry(expires_at)?;
+    }
+    Ok(license)
+}
+
+fn parse_expiry(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|time| time.and_utc())
+        .ok_or_else(|| format!("Invalid license expiry: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pandoc::crypto::decrypt_template;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    const CONTENT_KEY: &str = "shared-member-template-key";
+    const USER_KEY: &[u8] = b"per-user-secret-0123456789abcdef";
+
+    fn key_pair() -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(&[7u8; 32]).unwrap()
+    }
+
+    fn sign(key_pair: &Ed25519KeyPair, payload: &str) -> String {
+        serde_json::json!({
+            "payload": STANDARD.encode(payload),
+            "signature": STANDARD.encode(key_pair.sign(payload.as_bytes())),
+        })
+        .to_string()
+    }
+
+    /// 签发方：用用户密钥包装内容 key
+    fn wrap(license_id: &str, template_id: &str, content_key: &str) -> String {
+        let (cipher, context) =
+            wrapping_key(&STANDARD.encode(USER_KEY), license_id, template_id).unwrap();
+        let nonce = [5u8; WRAP_NONCE_LEN];
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: content_key.as_bytes(),
+                    aad: context.as_bytes(),
+                },
+            )
+            .unwrap();
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        STANDARD.encode(data)
+    }
+
+    /// 授权 L-1，为 `keyed` 中的每个模板包装 `content_key`
+    fn license_payload(
+        expires_at: &str,
+        templates: &[&str],
+        keyed: &[&str],
+        content_key: &str,
+    ) -> String {
+        let wrapped: BTreeMap<_, _> = keyed
+            .iter()
+            .map(|id| (id.to_string(), wrap("L-1", id, content_key)))
+            .collect();
+        serde_json::json!({
+            "id": "L-1",
+            "licensee": "tester",
+            "expires_at": expires_at,
+            "templates": templates,
+            "key": STANDARD.encode(USER_KEY),
+            "wrapped_keys": wrapped,
+        })
+        .to_string()
+    }
+
+    fn payload(expires_at: &str, templates: &[&str]) -> String {
+        license_payload(expires_at, templates, &["plain-member"], CONTENT_KEY)
+    }
+
+    fn verify(content: &str) -> Result<License, String> {
+        verify_license(content, key_pair().public_key().as_ref())
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn valid_signature_is_accepted() {
+        let license = verify(&sign(&key_pair(), &payload("2099-01-01", &["*"]))).unwrap();
+        assert_eq!(license.id, "L-1");
+        assert_eq!(
+            license.content_key("plain-member", Utc::now()).unwrap(),
+            CONTENT_KEY
+        );
+    }
+
+    #[test]
+    fn payload_does_not_contain_the_content_key() {
+        let payload = payload("2099-01-01", &["*"]);
+        assert!(!payload.contains(CONTENT_KEY));
+        assert!(!payload.contains(&STANDARD.encode(CONTENT_KEY)));
+    }
+
+    #[test]
+    fn modified_payload_is_rejected() {
+        let signed = sign(&key_pair(), &payload("2099-01-01", &["plain-member"]));
+        let mut file: serde_json::Value = serde_json::from_str(&signed).unwrap();
+        file["payload"] = STANDARD.encode(payload("2099-01-01", &["*"])).into();
+        assert_eq!(
+            verify(&file.to_string()).unwrap_err(),
+            "License signature is invalid"
+        );
+
+        let other = Ed25519KeyPair::from_seed_unchecked(&[8u8; 32]).unwrap();
+        assert!(verify(&sign(&other, &payload("2099-01-01", &["*"]))).is_err());
+    }
+
+    #[test]
+    fn expired_license_is_rejected() {
+        let license = verify(&sign(&key_pair(), &payload("2025-03-01", &["*"]))).unwrap();
+        // 只写日期时到当天结束才过期
+        assert!(license
+            .content_key("plain-member", at("2025-03-01T23:59:00Z"))
+            .is_ok());
+        let err = license
+            .content_key("plain-member", at("2025-03-02T00:00:00Z"))
+            .unwrap_err();
+        assert_eq!(err, "License expired on 2025-03-01");
+        assert!(
+            license
+                .status_at(at("2025-03-02T00:00:00Z"))
+                .unwrap()
+                .expired
+        );
+        assert!(!license.unlocks("plain-member", at("2025-03-02T00:00:00Z")));
+    }
+
+    #[test]
+    fn entitlement_by_wildcard_and_id() {
+        let now = Utc::now();
+        let all = verify(&sign(
+            &key_pair(),
+            &license_payload(
+                "2099-01-01",
+                &["*"],
+                &["plain-member", "thesis"],
+                CONTENT_KEY,
+            ),
+        ))
+        .unwrap();
+        assert!(all.content_key("thesis", now).is_ok());
+        assert!(all.unlocks("thesis", now));
+
+        let one = verify(&sign(
+            &key_pair(),
+            &license_payload(
+                "2099-01-01",
+                &["plain-member"],
+                &["plain-member", "thesis"],
+                CONTENT_KEY,
+            ),
+        ))
+        .unwrap();
+        assert!(one.content_key("plain-member", now).is_ok());
+        assert_eq!(
+            one.content_key("thesis", now).unwrap_err(),
+            "License does not include template 'thesis'"
+        );
+        assert!(!one.unlocks("thesis", now));
+    }
+
+    /// `*` 只表示授权范围，没有包装 key 的模板仍然无法解开
+    #[test]
+    fn wildcard_does_not_unlock_templates_without_a_key() {
+        let now = Utc::now();
+        let license = verify(&sign(&key_pair(), &payload("2099-01-01", &["*"]))).unwrap();
+        assert!(license.unlocks("plain-member", now));
+        assert!(!license.unlocks("thesis", now));
+        assert_eq!(
+            license.content_key("thesis", now).unwrap_err(),
+            "License has no key for template 'thesis'"
+        );
+    }
+
+    /// 包装结果绑定授权 ID 与模板 ID，挪到其他模板或其他授权下无法解开
+    #[test]
+    fn wrapped_keys_are_bound_to_license_and_template() {
+        let moved = serde_json::json!({
+            "id": "L-1",
+            "templates": ["*"],
+            "key": STANDARD.encode(USER_KEY),
+            "wrapped_keys": { "thesis": wrap("L-1", "plain-member", CONTENT_KEY) },
+        })
+        .to_string();
+        let license = verify(&sign(&key_pair(), &moved)).unwrap();
+        assert_eq!(
+            license.content_key("thesis", Utc::now()).unwrap_err(),
+            "Failed to unlock template 'thesis'"
+        );
+
+        let other_license = serde_json::json!({
+            "id": "L-2",
+            "templates": ["*"],
+            "key": STANDARD.encode(USER_KEY),
+            "wrapped_keys": { "plain-member": wrap("L-1", "plain-member", CONTENT_KEY) },
+        })
+        .to_string();
+        let license = verify(&sign(&key_pair(), &other_license)).unwrap();
+        assert!(license.content_key("plain-member", Utc::now()).is_err());
+    }
+
+    /// 现有的受保护模板是用同一个 key 加密的旧格式（IV + AES-256-CBC），授权携带该 key 后应能直接解密
+    #[test]
+    fn legacy_member_template_decrypts_with_license() {
+        use aes::Aes256;
+        use cbc::Encryptor;
+        use cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+        use sha2::{Digest, Sha256};
+
+        let manifest = env!("CARGO_MANIFEST_DIR");
+        let plain = fs::read(Path::new(manifest).join("resources/templates/plain")).unwrap();
+        let key = Sha256::digest(CONTENT_KEY.as_bytes());
+        let iv = [3u8; 16];
+        let mut buffer = plain.clone();
+        buffer.resize(plain.len() + 16, 0);
+        let ciphertext = Encryptor::<Aes256>::new((&key).into(), (&iv).into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, plain.len())
+            .unwrap()
+            .to_vec();
+        let mut protected = iv.to_vec();
+        protected.extend_from_slice(&ciphertext);
+
+        let license = verify(&sign(
+            &key_pair(),
+            &payload("2099-01-01", &["plain-member"]),
+        ))
+        .unwrap();
+        let content_key = license.content_key("plain-member", Utc::now()).unwrap();
+        assert_eq!(decrypt_template(&protected, &content_key).unwrap(), plain);
+    }
+
+    /// 随应用发布的 plain-member 需要发布时的模板 key
+    #[test]
+    #[ignore = "needs the release template key in FORMATSMAN_TEMPLATE_KEY"]
+    fn bundled_plain_member_decrypts_with_license() {
+        let shipped_key =
+            std::env::var("FORMATSMAN_TEMPLATE_KEY").expect("FORMATSMAN_TEMPLATE_KEY is not set");
+        let payload = license_payload(
+            "2099-01-01",
+            &["plain-member"],
+            &["plain-member"],
+            &shipped_key,
+        );
+        let license = verify(&sign(&key_pair(), &payload)).unwrap();
+        let content_key = license.content_key("plain-member", Utc::now()).unwrap();
+
+        let manifest = env!("CARGO_MANIFEST_DIR");
+        let data = fs::read(Path::new(manifest).join("resources/templates/plain-member")).unwrap();
+        let plain = decrypt_template(&data, &content_key).unwrap();
+        assert!(plain.starts_with(b"PK\x03\x04"));
+    }
+}
@@ -1,9 +1,39 @@
+pub mod attrs;
+pub mod book;
+pub mod callouts;
+pub mod chat;
+pub mod chinese;
+pub mod cjk;
 pub mod config;
 pub mod converter;
+pub mod crypto;
+pub mod docx;
 pub mod downloader;
+pub mod epub;
+pub mod frontmatter;
+pub mod generator;
+pub mod html;
+pub mod ignore;
 pub mod input;
+pub mod inspect;
+pub mod latex;
+pub mod library;
+pub mod license;
+pub mod notebook;
+pub mod overrides;
+pub mod packs;
+pub mod preview;
+pub mod reader;
+pub mod runtime;
+pub mod shortcodes;
+pub mod stylemap;
+pub mod styles;
+pub mod tables;
 pub mod templates;
 
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
 use tauri::{command, AppHandle, Window};
 
 use config::{get_crossref_download_urls, get_install_dir, get_pandoc_download_urls, PandocConfig};
@@ -11,10 +41,20 @@ use converter::{
     check_crossref_installed, check_pandoc_installed, convert_md_to_docx, delete_all_sessions,
     get_pandoc_version, ConvertOptions,
 };
+use crypto::{protect_template, ProtectedTemplate};
 use downloader::{download_with_fallback, extract_archive, find_executable_in_dir};
-use input::{prepare_input, InputSource, PreparedInput};
+use generator::{build_reference_doc, load_style_spec, StyleSpec};
+use input::{prepare_input, InputOptions, InputSource, PreparedInput};
+use inspect::{inspect_docx, inspect_file, TemplateReport};
+use library::{delete_template, import_template, rename_template, user_templates, UserTemplate};
+use license::LicenseStatus;
+use packs::{install_pack, list_packs, uninstall_pack, validate_pack, PackValidation, TemplatePack};
+use preview::{clear_previews, generate_preview, TemplatePreview};
+use stylemap::{CustomStyleIssue, CustomStyles};
+use styles::{docx_styles, read_docx_styles};
 use templates::{
-    list_templates as list_templates_impl, prepare_template, TemplateInfo, TemplateListResponse,
+    list_templates as list_templates_impl, prepare_generated_template, prepare_template,
+    read_template_bytes, TemplateInfo, TemplateListResponse,
 };
 
 #[command]
@@ -163,8 +203,9 @@ pub async fn convert_markdown(
 pub async fn prepare_input_payload(
     app_handle: AppHandle,
     source: InputSource,
+    options: Option<InputOptions>,
 ) -> Result<PreparedInput, String> {
-    prepare_input(&app_handle, source).await
+    prepare_input(&app_handle, source, options.unwrap_or_default()).await
 }
 
 #[allow(non_snake_case)]
@@ -172,18 +213,179 @@ pub async fn prepare_input_payload(
 pub fn prepare_template_protected(
     app_handle: AppHandle,
     templateName: String,
-    isMember: bool,
-    key: String,
 ) -> Result<TemplateInfo, String> {
     // Tauri v2 expects camelCase param names; use `templateName` here
-    prepare_template(&app_handle, &templateName, isMember, key)
+    // 是否可用、解密 key 均由后端按已安装的授权决定
+    prepare_template(&app_handle, &templateName)
+}
+
+/// 按样式描述生成参考文档，供转换直接使用
+#[command]
+pub fn prepare_template_from_spec(
+    app_handle: AppHandle,
+    spec: StyleSpec,
+) -> Result<TemplateInfo, String> {
+    prepare_generated_template(&app_handle, &spec)
+}
+
+/// 把 JSON/YAML 样式描述文件生成为 .docx 参考文档，返回输出路径
+#[command]
+pub fn generate_reference_doc(spec_path: String, output_path: String) -> Result<String, String> {
+    let spec = load_style_spec(Path::new(&spec_path))?;
+    let bytes = build_reference_doc(&spec)?;
+    std::fs::write(&output_path, bytes)
+        .map_err(|e| format!("Failed to write reference doc: {}", e))?;
+    Ok(output_path)
+}
+
+/// 检查内置模板的样式、字体与页面设置，受保护模板只在内存中解密
+#[allow(non_snake_case)]
+#[command]
+pub fn inspect_template(
+    app_handle: AppHandle,
+    templateName: String,
+) -> Result<TemplateReport, String> {
+    let bytes = read_template_bytes(&app_handle, &templateName)?;
+    inspect_docx(&bytes)
+}
+
+/// 用给定 key 加密 .docx 模板，写出后校验能否还原，供模板作者发布受保护模板
+#[command]
+pub fn protect_template_file(
+    input_path: String,
+    output_path: String,
+    key: String,
+) -> Result<ProtectedTemplate, String> {
+    protect_template(Path::new(&input_path), Path::new(&output_path), &key)
+}
+
+/// 检查任意模板文件，供模板作者发布前校验
+#[command]
+pub fn inspect_template_file(path: String, key: Option<String>) -> Result<TemplateReport, String> {
+    inspect_file(Path::new(&path), key)
+}
+
+/// 导入 .docx/.dotx 作为用户模板，正文内容会被去掉
+#[command]
+pub fn import_user_template(
+    app_handle: AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<UserTemplate, String> {
+    import_template(&app_handle, Path::new(&path), name)
+}
+
+#[command]
+pub fn rename_user_template(
+    app_handle: AppHandle,
+    id: String,
+    name: String,
+) -> Result<UserTemplate, String> {
+    rename_template(&app_handle, &id, &name)
+}
+
+#[command]
+pub fn delete_user_template(app_handle: AppHandle, id: String) -> Result<(), String> {
+    delete_template(&app_handle, &id)
+}
+
+#[command]
+pub fn list_user_templates(app_handle: AppHandle) -> Result<Vec<UserTemplate>, String> {
+    user_templates(&app_handle)
 }
 
+/// 校验模板包（zip），不安装
+#[command]
+pub fn validate_template_pack(path: String) -> Result<PackValidation, String> {
+    validate_pack(Path::new(&path))
+}
+
+/// 安装模板包，同 ID 的旧版本会被替换
+#[command]
+pub fn install_template_pack(app_handle: AppHandle, path: String) -> Result<TemplatePack, String> {
+    install_pack(&app_handle, Path::new(&path))
+}
+
+#[command]
+pub fn uninstall_template_pack(app_handle: AppHandle, id: String) -> Result<(), String> {
+    uninstall_pack(&app_handle, &id)
+}
+
+#[command]
+pub fn list_template_packs(app_handle: AppHandle) -> Result<Vec<TemplatePack>, String> {
+    list_packs(&app_handle)
+}
+
+/// 内置、用户与模板包的完整目录；受保护模板按当前授权检查能否解密
 #[command]
 pub fn list_templates(app_handle: AppHandle) -> Result<TemplateListResponse, String> {
     list_templates_impl(&app_handle)
 }
 
+/// 校验并安装会员授权文件，离线可用
+#[command]
+pub fn install_license(app_handle: AppHandle, path: String) -> Result<LicenseStatus, String> {
+    license::install_license(&app_handle, Path::new(&path))
+}
+
+/// 当前授权；未安装时为 null，授权文件无效时报错
+#[command]
+pub fn license_status(app_handle: AppHandle) -> Result<Option<LicenseStatus>, String> {
+    license::license_status(&app_handle)
+}
+
+#[command]
+pub fn remove_license(app_handle: AppHandle) -> Result<(), String> {
+    license::remove_license(&app_handle)
+}
+
+/// 按参考文档校验模板的 class → 样式映射，返回无法使用的项
+#[command]
+pub fn validate_custom_styles(
+    reference_doc: String,
+    custom_styles: BTreeMap<String, String>,
+) -> Result<Vec<CustomStyleIssue>, String> {
+    let styles = read_docx_styles(Path::new(&reference_doc))?;
+    Ok(CustomStyles::resolve(&custom_styles, &styles).1)
+}
+
+/// 按模板 ID 校验 class → 样式映射；受保护模板只在内存中解密
+#[command]
+pub fn validate_template_styles(
+    app_handle: AppHandle,
+    template_id: String,
+    custom_styles: BTreeMap<String, String>,
+) -> Result<Vec<CustomStyleIssue>, String> {
+    let styles = docx_styles(&read_template_bytes(&app_handle, &template_id)?)?;
+    Ok(CustomStyles::resolve(&custom_styles, &styles).1)
+}
+
+/// 用内置示例文档生成模板预览（DOCX 与近似的 HTML），按模板与预设缓存
+///
+/// `metadata` 为预设对应的 Pandoc 元数据；`refresh` 为 true 时忽略已有缓存。
+#[command]
+pub async fn generate_template_preview(
+    app_handle: AppHandle,
+    template_id: String,
+    metadata: Option<Value>,
+    use_crossref: Option<bool>,
+    refresh: Option<bool>,
+) -> Result<TemplatePreview, String> {
+    generate_preview(
+        &app_handle,
+        &template_id,
+        metadata,
+        use_crossref.unwrap_or(true),
+        refresh.unwrap_or(false),
+    )
+    .await
+}
+
+#[command]
+pub fn clear_template_previews(app_handle: AppHandle) -> Result<(), String> {
+    clear_previews(&app_handle)
+}
+
 #[command]
 pub async fn clear_sessions(app_handle: AppHandle) -> Result<(), String> {
     delete_all_sessions(&app_handle)
@@ -0,0 +1,235 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use super::attrs::Attributes;
+use super::input::make_unique_name;
+use super::reader::title_author_front_matter;
+use super::tables::{html_table_rows, render_table};
+
+/// 按优先级挑选输出中的图片类型
+const IMAGE_MIME_TYPES: [(&str, &str); 3] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/svg+xml", "svg"),
+];
+
+/// 将 .ipynb 转为 Markdown：Markdown 单元格保留原文，代码单元格按需输出，
+/// 图片输出与附件写入 `assets/`，HTML 表格转为 Markdown 表格。
+///
+/// 返回 (Markdown 文本, 写入的图片路径)
+pub fn notebook_to_markdown(
+    content: &str,
+    assets_dir: &Path,
+    stem: &str,
+    include_code: bool,
+) -> Result<(String, Vec<String>), String> {
+    let notebook: Value =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse notebook: {}", e))?;
+
+    let cells = notebook
+        .get("cells")
+        .and_then(|c| c.as_array())
+        .ok_or_else(|| "Notebook has no cells (only nbformat 4 is supported)".to_string())?;
+
+    let metadata = notebook.get("metadata").cloned().unwrap_or(Value::Null);
+    let language = metadata
+        .pointer("/kernelspec/language")
+        .or_else(|| metadata.pointer("/language_info/name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let mut writer = NotebookWriter {
+        assets_dir,
+        stem,
+        images: Vec::new(),
+    };
+    let mut blocks: Vec<String> = Vec::new();
+
+    if let Some(front_matter) = metadata_front_matter(&metadata)? {
+        blocks.push(front_matter);
+    }
+
+    for (index, cell) in cells.iter().enumerate() {
+        let source = join_text(cell.get("source"));
+        match cell.get("cell_type").and_then(|t| t.as_str()) {
+            Some("markdown") => {
+                let text = writer.replace_attachments(&source, cell.get("attachments"), index)?;
+                if !text.trim().is_empty() {
+                    blocks.push(text.trim_end().to_string());
+                }
+            }
+            Some("code") => {
+                if include_code && !source.trim().is_empty() {
+                    blocks.push(format!("```{}\n{}\n```", language, source.trim_end()));
+                }
+                if let Some(outputs) = cell.get("outputs").and_then(|o| o.as_array()) {
+                    for (out_idx, output) in outputs.iter().enumerate() {
+                        if let Some(block) = writer.render_output(output, index, out_idx)? {
+                            blocks.push(block);
+                        }
+                    }
+                }
+            }
+            // raw 单元格一般是给 nbconvert 的原始片段，直接跳过
+            _ => {}
+        }
+    }
+
+    let mut markdown = blocks.join("\n\n");
+    markdown.push('\n');
+    Ok((markdown, writer.images))
+}
+
+struct NotebookWriter<'a> {
+    assets_dir: &'a Path,
+    stem: &'a str,
+    images: Vec<String>,
+}
+
+impl NotebookWriter<'_> {
+    fn render_output(
+        &mut self,
+        output: &Value,
+        cell_idx: usize,
+        out_idx: usize,
+    ) -> Result<Option<String>, String> {
+        match output.get("output_type").and_then(|t| t.as_str()) {
+            Some("stream") => {
+                if output.get("name").and_then(|n| n.as_str()) == Some("stderr") {
+                    return Ok(None);
+                }
+                let text = join_text(output.get("text"));
+                Ok((!text.trim().is_empty()).then(|| format!("```\n{}\n```", text.trim_end())))
+            }
+            Some("execute_result") | Some("display_data") => {
+                let Some(data) = output.get("data") else {
+                    return Ok(None);
+                };
+
+                for (mime, ext) in IMAGE_MIME_TYPES {
+                    if let Some(payload) = data.get(mime) {
+                        let name =
+                            format!("{}-cell{}-{}.{}", self.stem, cell_idx + 1, out_idx + 1, ext);
+                        let file_name = self.write_image(&name, payload, mime)?;
+                        return Ok(Some(format!("![](assets/{})", file_name)));
+                    }
+                }
+
+                if let Some(markdown) = data.get("text/markdown") {
+                    return Ok(Some(join_text(Some(markdown)).trim_end().to_string()));
+                }
+
+                if let Some(html) = data.get("text/html") {
+                    let rows = html_table_rows(&join_text(Some(html)))?;
+                    if !rows.is_empty() {
+                        if let Ok(table) = render_table(rows, &Attributes::default()) {
+                            return Ok(Some(table));
+                        }
+                    }
+                }
+
+                if let Some(latex) = data.get("text/latex") {
+                    return Ok(Some(join_text(Some(latex)).trim().to_string()));
+                }
+
+                if let Some(plain) = data.get("text/plain") {
+                    let text = join_text(Some(plain));
+                    if !text.trim().is_empty() {
+                        return Ok(Some(format!("```\n{}\n```", text.trim_end())));
+                    }
+                }
+                Ok(None)
+            }
+            // 错误输出（traceback）不写入文档
+            _ => Ok(None),
+        }
+    }
+
+    /// 单元格附件以 `attachment:name` 引用，写出后替换为 assets 路径
+    fn replace_attachments(
+        &mut self,
+        source: &str,
+        attachments: Option<&Value>,
+        cell_idx: usize,
+    ) -> Result<String, String> {
+        let Some(attachments) = attachments.and_then(|a| a.as_object()) else {
+            return Ok(source.to_string());
+        };
+
+        let mut text = source.to_string();
+        for (name, bundle) in attachments {
+            // 附件名来自 notebook 文件，只接受不含路径的文件名
+            let Some(name) = attachment_file_name(name) else {
+                log::warn!("Skipping notebook attachment with unsafe name: {}", name);
+                continue;
+            };
+            let Some((mime, payload)) = IMAGE_MIME_TYPES
+                .iter()
+                .find_map(|(mime, _)| bundle.get(*mime).map(|p| (*mime, p)))
+            else {
+                continue;
+            };
+            let target = format!("{}-cell{}-{}", self.stem, cell_idx + 1, name);
+            let file_name = self.write_image(&target, payload, mime)?;
+            text = text.replace(
+                &format!("attachment:{}", name),
+                &format!("assets/{}", file_name),
+            );
+        }
+        Ok(text)
+    }
+
+    fn write_image(&mut self, name: &str, payload: &Value, mime: &str) -> Result<String, String> {
+        let raw = join_text(Some(payload));
+        let bytes = if mime == "image/svg+xml" {
+            raw.into_bytes()
+        } else {
+            let compact: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+            BASE64
+                .decode(compact)
+                .map_err(|e| format!("Failed to decode notebook image: {}", e))?
+        };
+
+        let file_name = make_unique_name(name, self.assets_dir);
+        let target = self.assets_dir.join(&file_name);
+        fs::write(&target, bytes).map_err(|e| format!("Failed to write notebook image: {}", e))?;
+        self.images.push(target.to_string_lossy().to_string());
+        Ok(file_name)
+    }
+}
+
+/// 附件名只能是单纯的文件名：含路径分隔符（`/` 或 `\\`，与平台无关）、为空或为 `.`/`..` 时返回 None
+fn attachment_file_name(name: &str) -> Option<&str> {
+    let base = Path::new(name).file_name()?.to_str()?;
+    let valid = base == name && !name.contains(['/', '\\']) && base != "." && base != "..";
+    valid.then_some(base)
+}
+
+/// nbformat 中的文本既可能是字符串，也可能是按行拆分的数组
+fn join_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts.iter().filter_map(|p| p.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+/// 从 notebook 元数据生成 title/author front matter
+fn metadata_front_matter(metadata: &Value) -> Result<Option<String>, String> {
+    let title = metadata.get("title").and_then(|t| t.as_str());
+    let authors: Vec<String> = match metadata.get("authors").or_else(|| metadata.get("author")) {
+        Some(Value::String(name)) => vec![name.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(name) => Some(name.clone()),
+                other => other.get("name").and_then(|n| n.as_str()).map(String::from),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    title_author_front_matter(title, &authors)
+}
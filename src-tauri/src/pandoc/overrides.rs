@@ -0,0 +1,752 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::docx::DocxPackage;
+use super::generator::{hex_color, mm_to_twips, twips, MarginSpec, TextSpec};
+use super::inspect::PAPER_SIZES;
+use super::styles::{escape_xml, xml_attr, xml_element};
+
+/// w:style 子元素顺序
+const STYLE_ORDER: [&str; 22] = [
+    "w:name",
+    "w:aliases",
+    "w:basedOn",
+    "w:next",
+    "w:link",
+    "w:autoRedefine",
+    "w:hidden",
+    "w:uiPriority",
+    "w:semiHidden",
+    "w:unhideWhenUsed",
+    "w:qFormat",
+    "w:locked",
+    "w:personal",
+    "w:personalCompose",
+    "w:personalReply",
+    "w:rsid",
+    "w:pPr",
+    "w:rPr",
+    "w:tblPr",
+    "w:trPr",
+    "w:tcPr",
+    "w:tblStylePr",
+];
+
+/// w:pPr 子元素顺序
+const PARAGRAPH_ORDER: [&str; 36] = [
+    "w:pStyle",
+    "w:keepNext",
+    "w:keepLines",
+    "w:pageBreakBefore",
+    "w:framePr",
+    "w:widowControl",
+    "w:numPr",
+    "w:suppressLineNumbers",
+    "w:pBdr",
+    "w:shd",
+    "w:tabs",
+    "w:suppressAutoHyphens",
+    "w:kinsoku",
+    "w:wordWrap",
+    "w:overflowPunct",
+    "w:topLinePunct",
+    "w:autoSpaceDE",
+    "w:autoSpaceDN",
+    "w:bidi",
+    "w:adjustRightInd",
+    "w:snapToGrid",
+    "w:spacing",
+    "w:ind",
+    "w:contextualSpacing",
+    "w:mirrorIndents",
+    "w:suppressOverlap",
+    "w:jc",
+    "w:textDirection",
+    "w:textAlignment",
+    "w:textboxTightWrap",
+    "w:outlineLvl",
+    "w:divId",
+    "w:cnfStyle",
+    "w:rPr",
+    "w:sectPr",
+    "w:pPrChange",
+];
+
+/// w:rPr 子元素顺序
+const RUN_ORDER: [&str; 39] = [
+    "w:rStyle",
+    "w:rFonts",
+    "w:b",
+    "w:bCs",
+    "w:i",
+    "w:iCs",
+    "w:caps",
+    "w:smallCaps",
+    "w:strike",
+    "w:dstrike",
+    "w:outline",
+    "w:shadow",
+    "w:emboss",
+    "w:imprint",
+    "w:noProof",
+    "w:snapToGrid",
+    "w:vanish",
+    "w:webHidden",
+    "w:color",
+    "w:spacing",
+    "w:w",
+    "w:kern",
+    "w:position",
+    "w:sz",
+    "w:szCs",
+    "w:highlight",
+    "w:u",
+    "w:effect",
+    "w:bdr",
+    "w:shd",
+    "w:fitText",
+    "w:vertAlign",
+    "w:rtl",
+    "w:cs",
+    "w:em",
+    "w:lang",
+    "w:eastAsianLayout",
+    "w:specVanish",
+    "w:oMath",
+];
+
+/// w:sectPr 子元素顺序
+const SECTION_ORDER: [&str; 22] = [
+    "w:headerReference",
+    "w:footerReference",
+    "w:footnotePr",
+    "w:endnotePr",
+    "w:type",
+    "w:pgSz",
+    "w:pgMar",
+    "w:paperSrc",
+    "w:pgBorders",
+    "w:lnNumType",
+    "w:pgNumType",
+    "w:cols",
+    "w:formProt",
+    "w:vAlign",
+    "w:noEndnote",
+    "w:titlePg",
+    "w:textDirection",
+    "w:bidi",
+    "w:rtlGutter",
+    "w:docGrid",
+    "w:printerSettings",
+    "w:sectPrChange",
+];
+
+/// 单次转换的样式覆盖，只作用于 runtime 目录中的模板副本
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleOverrides {
+    /// docDefaults：未单独设置的样式都会继承
+    pub defaults: Option<TextSpec>,
+    /// 按样式名或 ID 覆盖（不区分大小写），例如 "Normal"、"Heading 1"
+    pub styles: BTreeMap<String, TextSpec>,
+    pub page: Option<PageOverride>,
+}
+
+impl StyleOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.defaults.is_none() && self.styles.is_empty() && self.page.is_none()
+    }
+}
+
+/// 页面设置覆盖，未给出的项保持模板原值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageOverride {
+    /// 纸张名称，例如 A4、Letter；给出宽高时以宽高为准
+    pub size: Option<String>,
+    pub width_mm: Option<f32>,
+    pub height_mm: Option<f32>,
+    pub landscape: Option<bool>,
+    pub margins: MarginSpec,
+}
+
+/// 将覆盖写入 .docx 的 styles.xml 与正文最后的 sectPr，返回新的包内容
+pub fn apply_style_overrides(bytes: &[u8], overrides: &StyleOverrides) -> Result<Vec<u8>, String> {
+    let mut package = DocxPackage::from_bytes(bytes)?;
+
+    if overrides.defaults.is_some() || !overrides.styles.is_empty() {
+        let styles = package
+            .part("word/styles.xml")
+            .ok_or("Reference doc has no styles.xml")?;
+        let mut styles = match &overrides.defaults {
+            Some(text) => patch_doc_defaults(&styles, text)?,
+            None => styles,
+        };
+        for (key, text) in &overrides.styles {
+            match patch_named_style(&styles, key, text)? {
+                Some(patched) => styles = patched,
+                None => log::warn!("Skipping style override \"{}\": style not found", key),
+            }
+        }
+        package.set_part("word/styles.xml", styles);
+    }
+
+    if let Some(page) = &overrides.page {
+        let document = package
+            .part("word/document.xml")
+            .ok_or("Not a Word document: missing word/document.xml")?;
+        package.set_part("word/document.xml", patch_page(&document, page)?);
+    }
+
+    package.to_bytes()
+}
+
+fn patch_doc_defaults(styles: &str, text: &TextSpec) -> Result<String, String> {
+    let (start, end) = match element_span(styles, 0, "w:docDefaults") {
+        Some(span) => span,
+        None => {
+            // docDefaults 须是 w:styles 的第一个子元素
+            let root = xml_element(styles, "w:styles").ok_or("Invalid styles.xml")?;
+            let at = styles.find(root).unwrap_or(0) + root.len();
+            let patched = format!(
+                "{}<w:docDefaults></w:docDefaults>{}",
+                &styles[..at],
+                &styles[at..]
+            );
+            return patch_doc_defaults(&patched, text);
+        }
+    };
+
+    let patched = patch_container(
+        &styles[start..end],
+        &["w:rPrDefault", "w:pPrDefault"],
+        |defaults| {
+            if has_run_changes(text) {
+                defaults.update_child("w:rPrDefault", |wrapper| {
+                    wrapper.update_child("w:rPr", |run| patch_run(run, text))
+                })?;
+            }
+            if has_paragraph_changes(text) {
+                defaults.update_child("w:pPrDefault", |wrapper| {
+                    wrapper.update_child("w:pPr", |paragraph| patch_paragraph(paragraph, text))
+                })?;
+            }
+            Ok(())
+        },
+    )?;
+    Ok(format!("{}{}{}", &styles[..start], patched, &styles[end..]))
+}
+
+/// 按名称或 ID 找到样式并覆盖；样式不存在时返回 None
+fn patch_named_style(styles: &str, key: &str, text: &TextSpec) -> Result<Option<String>, String> {
+    let mut from = 0;
+    while let Some((start, end)) = element_span(styles, from, "w:style") {
+        let style = &styles[start..end];
+        let open = xml_element(style, "w:style").unwrap_or("");
+        let id = xml_attr(open, "w:styleId").unwrap_or_default();
+        let name = xml_element(style, "w:name")
+            .and_then(|tag| xml_attr(tag, "w:val"))
+            .unwrap_or_default();
+        if id.eq_ignore_ascii_case(key) || name.eq_ignore_ascii_case(key) {
+            // 字符样式没有段落属性
+            let paragraph = xml_attr(open, "w:type").as_deref() != Some("character");
+            let patched = patch_container(style, &STYLE_ORDER, |children| {
+                if paragraph && has_paragraph_changes(text) {
+                    children.update_child("w:pPr", |props| patch_paragraph(props, text))?;
+                }
+                if has_run_changes(text) {
+                    children.update_child("w:rPr", |props| patch_run(props, text))?;
+                }
+                Ok(())
+            })?;
+            return Ok(Some(format!(
+                "{}{}{}",
+                &styles[..start],
+                patched,
+                &styles[end..]
+            )));
+        }
+        from = end;
+    }
+    Ok(None)
+}
+
+fn has_paragraph_changes(text: &TextSpec) -> bool {
+    text.align.is_some()
+        || text.line_spacing.is_some()
+        || text.first_line_indent.is_some()
+        || text.left_indent.is_some()
+        || text.space_before.is_some()
+        || text.space_after.is_some()
+}
+
+fn has_run_changes(text: &TextSpec) -> bool {
+    text.font.is_some()
+        || text.latin_font.is_some()
+        || text.size.is_some()
+        || text.bold.is_some()
+        || text.italic.is_some()
+        || text.color.is_some()
+}
+
+fn patch_paragraph(props: &mut Children, text: &TextSpec) -> Result<(), String> {
+    // *Lines / *Chars 属性优先于绝对值，覆盖时一并去掉
+    let mut spacing = Vec::new();
+    if let Some(before) = text.space_before {
+        spacing.push(("w:before", Some(twips(before).to_string())));
+        spacing.push(("w:beforeLines", None));
+        spacing.push(("w:beforeAutospacing", None));
+    }
+    if let Some(after) = text.space_after {
+        spacing.push(("w:after", Some(twips(after).to_string())));
+        spacing.push(("w:afterLines", None));
+        spacing.push(("w:afterAutospacing", None));
+    }
+    if let Some(line) = &text.line_spacing {
+        let (value, rule) = line.attrs()?;
+        spacing.push(("w:line", Some(value.to_string())));
+        spacing.push(("w:lineRule", Some(rule.to_string())));
+    }
+    if !spacing.is_empty() {
+        props.update_attrs("w:spacing", &spacing);
+    }
+
+    let mut indent = Vec::new();
+    if let Some(chars) = text.left_indent {
+        indent.push(("w:leftChars", Some((chars * 100.0).round().to_string())));
+        indent.push(("w:left", Some("0".to_string())));
+        indent.push(("w:startChars", None));
+        indent.push(("w:start", None));
+    }
+    if let Some(chars) = text.first_line_indent {
+        indent.push((
+            "w:firstLineChars",
+            Some((chars * 100.0).round().to_string()),
+        ));
+        indent.push(("w:firstLine", Some("0".to_string())));
+        indent.push(("w:hangingChars", None));
+        indent.push(("w:hanging", None));
+    }
+    if !indent.is_empty() {
+        props.update_attrs("w:ind", &indent);
+    }
+
+    if let Some(align) = text.align {
+        props.set("w:jc", format!(r#"<w:jc w:val="{}"/>"#, align.value()));
+    }
+    Ok(())
+}
+
+fn patch_run(props: &mut Children, text: &TextSpec) -> Result<(), String> {
+    // 主题字体属性优先于字体名，覆盖时一并去掉
+    let mut fonts = Vec::new();
+    if let Some(latin) = &text.latin_font {
+        for (slot, theme) in [
+            ("w:ascii", "w:asciiTheme"),
+            ("w:hAnsi", "w:hAnsiTheme"),
+            ("w:cs", "w:cstheme"),
+        ] {
+            fonts.push((slot, Some(latin.clone())));
+            fonts.push((theme, None));
+        }
+    }
+    if let Some(east_asia) = &text.font {
+        fonts.push(("w:eastAsia", Some(east_asia.clone())));
+        fonts.push(("w:eastAsiaTheme", None));
+    }
+    if !fonts.is_empty() {
+        props.update_attrs("w:rFonts", &fonts);
+    }
+
+    let toggle = |tag: &str, on: bool| {
+        if on {
+            format!("<{}/>", tag)
+        } else {
+            format!(r#"<{} w:val="0"/>"#, tag)
+        }
+    };
+    if let Some(bold) = text.bold {
+        props.set("w:b", toggle("w:b", bold));
+        props.set("w:bCs", toggle("w:bCs", bold));
+    }
+    if let Some(italic) = text.italic {
+        props.set("w:i", toggle("w:i", italic));
+        props.set("w:iCs", toggle("w:iCs", italic));
+    }
+    if let Some(color) = &text.color {
+        props.set(
+            "w:color",
+            format!(r#"<w:color w:val="{}"/>"#, hex_color(color)?),
+        );
+    }
+    if let Some(size) = &text.size {
+        let half_points = (size.points()? * 2.0).round();
+        props.set("w:sz", format!(r#"<w:sz w:val="{}"/>"#, half_points));
+        props.set("w:szCs", format!(r#"<w:szCs w:val="{}"/>"#, half_points));
+    }
+    Ok(())
+}
+
+fn patch_page(document: &str, page: &PageOverride) -> Result<String, String> {
+    // 正文最后的 sectPr 决定 pandoc 输出的页面设置
+    let start = document
+        .rfind("<w:sectPr")
+        .ok_or("Reference doc has no section properties")?;
+    let (start, end) =
+        element_span(document, start, "w:sectPr").ok_or("Invalid section properties")?;
+
+    let patched = patch_container(&document[start..end], &SECTION_ORDER, |section| {
+        let current = section.get("w:pgSz").unwrap_or("");
+        let current_size = |name: &str| {
+            xml_attr(current, name)
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(0)
+        };
+        let (mut width, mut height) = (current_size("w:w"), current_size("w:h"));
+        let was_landscape =
+            xml_attr(current, "w:orient").as_deref() == Some("landscape") || width > height;
+
+        match (page.width_mm, page.height_mm) {
+            (Some(w), Some(h)) => (width, height) = (mm_to_twips(w), mm_to_twips(h)),
+            (None, None) => {
+                if let Some(name) = &page.size {
+                    (width, height) = PAPER_SIZES
+                        .iter()
+                        .find(|(n, _, _)| n.eq_ignore_ascii_case(name))
+                        .map(|(_, w, h)| (*w as i64, *h as i64))
+                        .ok_or_else(|| format!("Unknown paper size: {}", name))?;
+                }
+            }
+            _ => return Err("Page width and height must be given together".to_string()),
+        }
+
+        let landscape = page.landscape.unwrap_or(was_landscape);
+        if landscape != (width > height) && width != height {
+            std::mem::swap(&mut width, &mut height);
+        }
+        if width <= 0 || height <= 0 {
+            return Err("Reference doc has no page size".to_string());
+        }
+        section.update_attrs(
+            "w:pgSz",
+            &[
+                ("w:w", Some(width.to_string())),
+                ("w:h", Some(height.to_string())),
+                ("w:orient", landscape.then(|| "landscape".to_string())),
+            ],
+        );
+
+        let margins = &page.margins;
+        let margin_attrs: Vec<(&str, Option<String>)> = [
+            ("w:top", margins.top),
+            ("w:bottom", margins.bottom),
+            ("w:left", margins.left),
+            ("w:right", margins.right),
+            ("w:header", margins.header),
+            ("w:footer", margins.footer),
+        ]
+        .into_iter()
+        .filter_map(|(name, mm)| mm.map(|mm| (name, Some(mm_to_twips(mm).to_string()))))
+        .collect();
+        if !margin_attrs.is_empty() {
+            section.update_attrs("w:pgMar", &margin_attrs);
+        }
+        Ok(())
+    })?;
+    Ok(format!(
+        "{}{}{}",
+        &document[..start],
+        patched,
+        &document[end..]
+    ))
+}
+
+/// 按 `order` 排列的子元素列表，保留未识别的元素及其位置
+struct Children<'o> {
+    order: &'o [&'o str],
+    items: Vec<(String, String)>,
+}
+
+impl<'o> Children<'o> {
+    fn parse(inner: &str, order: &'o [&'o str]) -> Self {
+        let mut items = Vec::new();
+        let mut from = 0;
+        while let Some(pos) = inner[from..].find('<') {
+            let start = from + pos;
+            let name: String = inner[start + 1..]
+                .chars()
+                .take_while(|c| !c.is_whitespace() && *c != '/' && *c != '>')
+                .collect();
+            match element_span(inner, start, &name) {
+                Some((_, end)) => {
+                    items.push((name, inner[start..end].to_string()));
+                    from = end;
+                }
+                None => {
+                    // 注释等无法识别的内容原样保留
+                    items.push((String::new(), inner[start..].to_string()));
+                    break;
+                }
+            }
+        }
+        Self { order, items }
+    }
+
+    fn get(&self, tag: &str) -> Option<&str> {
+        self.items
+            .iter()
+            .find(|(name, _)| name == tag)
+            .map(|(_, xml)| xml.as_str())
+    }
+
+    /// 替换子元素，不存在时按顺序插入
+    fn set(&mut self, tag: &str, xml: String) {
+        if let Some(item) = self.items.iter_mut().find(|(name, _)| name == tag) {
+            item.1 = xml;
+            return;
+        }
+        let rank = |name: &str| self.order.iter().position(|o| *o == name);
+        let own = rank(tag).unwrap_or(self.order.len());
+        let at = self
+            .items
+            .iter()
+            .position(|(name, _)| rank(name).is_some_and(|r| r > own))
+            .unwrap_or(self.items.len());
+        self.items.insert(at, (tag.to_string(), xml));
+    }
+
+    /// 修改子元素的属性（None 表示删除），保留其余属性
+    fn update_attrs(&mut self, tag: &str, attrs: &[(&str, Option<String>)]) {
+        let mut xml = self
+            .get(tag)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("<{}/>", tag));
+        for (name, value) in attrs {
+            xml = set_attr(&xml, name, value.as_deref());
+        }
+        self.set(tag, xml);
+    }
+
+    /// 在子元素内部继续修改，保留其开始标签上的属性
+    fn update_child(
+        &mut self,
+        tag: &str,
+        patch: impl FnOnce(&mut Children) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let order: &[&str] = match tag {
+            "w:pPr" => &PARAGRAPH_ORDER,
+            "w:rPr" => &RUN_ORDER,
+            _ => &[],
+        };
+        let existing = self
+            .get(tag)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("<{}/>", tag));
+        let patched = patch_container(&existing, order, patch)?;
+        self.set(tag, patched);
+        Ok(())
+    }
+
+    fn render(&self) -> String {
+        self.items.iter().map(|(_, xml)| xml.as_str()).collect()
+    }
+}
+
+/// 拆开一个元素，修改其子元素后重新拼接
+fn patch_container(
+    element: &str,
+    order: &[&str],
+    patch: impl FnOnce(&mut Children) -> Result<(), String>,
+) -> Result<String, String> {
+    let open_end = element.find('>').ok_or("Invalid XML element")? + 1;
+    let open = &element[..open_end];
+    let name: String = open[1..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '/' && *c != '>')
+        .collect();
+
+    let (open, inner) = match open.strip_suffix("/>") {
+        Some(attrs) => (format!("{}>", attrs.trim_end()), ""),
+        None => {
+            let close = format!("</{}>", name);
+            let inner_end = element.len() - close.len();
+            (open.to_string(), &element[open_end..inner_end])
+        }
+    };
+
+    let mut children = Children::parse(inner, order);
+    patch(&mut children)?;
+    Ok(format!("{}{}</{}>", open, children.render(), name))
+}
+
+/// 从 `from` 起第一个 `tag` 元素的字节范围（含结束标签），处理自闭合与同名嵌套
+fn element_span(xml: &str, from: usize, tag: &str) -> Option<(usize, usize)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let is_open = |at: usize| {
+        xml[at..].starts_with(&open)
+            && xml[at + open.len()..].starts_with([' ', '/', '>', '\t', '\r', '\n'])
+    };
+
+    let mut start = from;
+    loop {
+        start += xml[start..].find(&open)?;
+        if is_open(start) {
+            break;
+        }
+        start += open.len();
+    }
+
+    let open_end = start + xml[start..].find('>')? + 1;
+    if xml[..open_end].ends_with("/>") {
+        return Some((start, open_end));
+    }
+
+    let mut depth = 1;
+    let mut cursor = open_end;
+    while depth > 0 {
+        let next_close = cursor + xml[cursor..].find(&close)?;
+        let next_open = xml[cursor..next_close]
+            .match_indices(&open)
+            .map(|(i, _)| cursor + i)
+            .find(|&i| is_open(i));
+        match next_open {
+            Some(nested) => {
+                let nested_end = nested + xml[nested..].find('>')? + 1;
+                if !xml[..nested_end].ends_with("/>") {
+                    depth += 1;
+                }
+                cursor = nested_end;
+            }
+            None => {
+                depth -= 1;
+                cursor = next_close + close.len();
+            }
+        }
+    }
+    Some((start, cursor))
+}
+
+/// 设置或删除开始标签上的属性
+fn set_attr(element: &str, name: &str, value: Option<&str>) -> String {
+    let open_end = element.find('>').unwrap_or(element.len());
+    let (open, rest) = element.split_at(open_end);
+
+    let key = format!(" {}=\"", name);
+    let mut open = open.to_string();
+    if let Some(pos) = open.find(&key) {
+        let value_start = pos + key.len();
+        if let Some(len) = open[value_start..].find('"') {
+            open.replace_range(pos..value_start + len + 1, "");
+        }
+    }
+
+    if let Some(value) = value {
+        let at = if open.ends_with('/') {
+            open.len() - 1
+        } else {
+            open.len()
+        };
+        open.insert_str(at, &format!(r#" {}="{}""#, name, escape_xml(value)));
+    }
+    format!("{}{}", open, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pandoc::generator::{build_reference_doc, FontSize, StyleSpec};
+    use crate::pandoc::inspect::inspect_docx;
+    use crate::pandoc::styles::{docx_styles, DocxStyle};
+
+    fn template() -> Vec<u8> {
+        build_reference_doc(&StyleSpec::default()).unwrap()
+    }
+
+    fn style<'s>(styles: &'s [DocxStyle], name: &str) -> &'s DocxStyle {
+        styles.iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[test]
+    fn named_styles_match_name_or_id_ignoring_case() {
+        let mut overrides = StyleOverrides::default();
+        overrides.styles.insert(
+            "HEADING1".to_string(),
+            TextSpec {
+                font: Some("黑体".to_string()),
+                size: Some(FontSize::Named("三号".to_string())),
+                ..Default::default()
+            },
+        );
+        overrides.styles.insert(
+            "body text".to_string(),
+            TextSpec {
+                size: Some(FontSize::Points(11.0)),
+                ..Default::default()
+            },
+        );
+        let styles = docx_styles(&apply_style_overrides(&template(), &overrides).unwrap()).unwrap();
+
+        let heading = style(&styles, "heading 1");
+        assert_eq!(heading.fonts.east_asia.as_deref(), Some("黑体"));
+        assert_eq!(heading.size_pt, Some(16.0));
+        assert_eq!(style(&styles, "Body Text").size_pt, Some(11.0));
+        // 未覆盖的样式保持不变
+        assert_eq!(style(&styles, "Normal").size_pt, Some(12.0));
+    }
+
+    #[test]
+    fn unknown_styles_are_skipped() {
+        let mut overrides = StyleOverrides::default();
+        overrides
+            .styles
+            .insert("No Such Style".to_string(), TextSpec::default());
+        let original = docx_styles(&template()).unwrap();
+        let patched =
+            docx_styles(&apply_style_overrides(&template(), &overrides).unwrap()).unwrap();
+        assert_eq!(original.len(), patched.len());
+    }
+
+    #[test]
+    fn defaults_and_page_are_patched() {
+        let overrides = StyleOverrides {
+            defaults: Some(TextSpec {
+                latin_font: Some("Arial".to_string()),
+                ..Default::default()
+            }),
+            page: Some(PageOverride {
+                size: Some("Letter".to_string()),
+                landscape: Some(true),
+                margins: MarginSpec {
+                    top: Some(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let report =
+            inspect_docx(&apply_style_overrides(&template(), &overrides).unwrap()).unwrap();
+        assert_eq!(report.fonts.default_fonts.ascii.as_deref(), Some("Arial"));
+
+        let page = report.page.unwrap();
+        assert_eq!(page.paper.as_deref(), Some("Letter"));
+        assert!(page.landscape);
+        assert!((page.margins.top - 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn page_width_requires_height() {
+        let overrides = StyleOverrides {
+            page: Some(PageOverride {
+                width_mm: Some(200.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_style_overrides(&template(), &overrides).unwrap_err(),
+            "Page width and height must be given together"
+        );
+    }
+}
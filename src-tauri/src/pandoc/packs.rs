@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use tauri::AppHandle;
+
+use super::inspect::inspect_docx;
+use super::library::user_templates_dir;
+use super::templates::{find_bundled_template, TemplateMeta};
+
+const MANIFEST_NAME: &str = "manifest.json";
+/// 解压后的总大小上限
+const MAX_PACK_SIZE: u64 = 100 * 1024 * 1024;
+const PREVIEW_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "svg"];
+
+/// 模板包清单（manifest.json）：模板元数据加上包内文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    #[serde(flatten)]
+    pub meta: TemplateMeta,
+    #[serde(default)]
+    pub version: String,
+    pub files: PackFiles,
+}
+
+/// 包内文件的相对路径
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PackFiles {
+    pub reference_doc: String,
+    /// 参考文档是否为受保护（加密）格式
+    pub encrypted: bool,
+    /// 默认预设的 JSON 文件，安装时写入 defaultPreset
+    pub preset: Option<String>,
+    /// 按顺序传给 pandoc 的 Lua 过滤器
+    pub filters: Vec<String>,
+    pub csl: Option<String>,
+    /// 示例文档（Markdown）
+    pub sample: Option<String>,
+    pub preview: Option<String>,
+}
+
+/// 校验结果：有 errors 的包不能安装，warnings 仅作提示
+#[derive(Debug, Clone, Serialize)]
+pub struct PackValidation {
+    pub manifest: Option<PackManifest>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// 已安装的模板包，路径均为绝对路径
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplatePack {
+    pub manifest: PackManifest,
+    pub dir: String,
+    pub reference_doc: String,
+    pub filters: Vec<String>,
+    pub csl: Option<String>,
+    pub sample: Option<String>,
+    pub preview: Option<String>,
+}
+
+pub fn packs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(user_templates_dir(app)?.join("packs"))
+}
+
+/// 已安装模板包的参考文档及其是否加密；未安装时返回 None
+pub fn pack_reference_doc(app: &AppHandle, id: &str) -> Result<Option<(PathBuf, bool)>, String> {
+    if !is_pack_id(id) {
+        return Ok(None);
+    }
+    let dir = packs_dir(app)?.join(id);
+    if !dir.join(MANIFEST_NAME).exists() {
+        return Ok(None);
+    }
+    let manifest = read_manifest(&dir)?;
+    let path = dir.join(&manifest.files.reference_doc);
+    Ok(path.exists().then_some((path, manifest.files.encrypted)))
+}
+
+/// 按 ID 查找已安装的模板包
+pub fn find_pack(app: &AppHandle, id: &str) -> Result<Option<TemplatePack>, String> {
+    if !is_pack_id(id) {
+        return Ok(None);
+    }
+    let dir = packs_dir(app)?.join(id);
+    if !dir.join(MANIFEST_NAME).exists() {
+        return Ok(None);
+    }
+    installed_pack(&dir).map(Some)
+}
+
+/// 校验模板包，不写入任何文件
+pub fn validate_pack(path: &Path) -> Result<PackValidation, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read template pack: {}", e))?;
+    Ok(check_pack(&read_entries(&bytes)?))
+}
+
+/// 安装模板包；同 ID 的包会被替换
+pub fn install_pack(app: &AppHandle, path: &Path) -> Result<TemplatePack, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read template pack: {}", e))?;
+    let entries = read_entries(&bytes)?;
+    let validation = check_pack(&entries);
+    if !validation.errors.is_empty() {
+        return Err(format!(
+            "Invalid template pack: {}",
+            validation.errors.join("; ")
+        ));
+    }
+    let mut manifest = validation
+        .manifest
+        .ok_or("Invalid template pack: missing manifest")?;
+    for warning in &validation.warnings {
+        log::warn!("Template pack {}: {}", manifest.meta.id, warning);
+    }
+
+    // 预设文件合并进清单，之后只需读取 manifest.json
+    if let Some(preset) = &manifest.files.preset {
+        if manifest.meta.defaultPreset.is_null() {
+            manifest.meta.defaultPreset = serde_json::from_slice(&entries[preset])
+                .map_err(|e| format!("Invalid preset {}: {}", preset, e))?;
+        }
+    }
+
+    let id = manifest.meta.id.clone();
+    if find_bundled_template(app, &id).is_some() {
+        return Err(format!(
+            "Template pack id '{}' conflicts with a bundled template",
+            id
+        ));
+    }
+    let root = packs_dir(app)?;
+    // 先解压到临时目录，完成后再替换旧版本，避免留下不完整的包
+    let staging = root.join(format!(".{}-installing", id));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)
+            .map_err(|e| format!("Failed to clean staging directory: {}", e))?;
+    }
+    for (name, data) in &entries {
+        let target = staging.join(name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create pack directory: {}", e))?;
+        }
+        fs::write(&target, data).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(staging.join(MANIFEST_NAME), json)
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    let dir = root.join(&id);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to replace template pack: {}", e))?;
+    }
+    fs::rename(&staging, &dir).map_err(|e| format!("Failed to install template pack: {}", e))?;
+    installed_pack(&dir)
+}
+
+pub fn uninstall_pack(app: &AppHandle, id: &str) -> Result<(), String> {
+    if !is_pack_id(id) {
+        return Err(format!("Invalid template pack id: {}", id));
+    }
+    let dir = packs_dir(app)?.join(id);
+    if !dir.exists() {
+        return Err(format!("Template pack '{}' not found", id));
+    }
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove template pack: {}", e))
+}
+
+/// 按名称排列的已安装模板包，清单损坏的会被跳过
+pub fn list_packs(app: &AppHandle) -> Result<Vec<TemplatePack>, String> {
+    let root = packs_dir(app)?;
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut packs = Vec::new();
+    let entries =
+        fs::read_dir(&root).map_err(|e| format!("Failed to read pack directory: {}", e))?;
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let is_pack = dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(is_pack_id);
+        if !is_pack || !dir.join(MANIFEST_NAME).exists() {
+            continue;
+        }
+        match installed_pack(&dir) {
+            Ok(pack) => packs.push(pack),
+            Err(e) => log::warn!("Skipping template pack {}: {}", dir.display(), e),
+        }
+    }
+    packs.sort_by(|a, b| a.manifest.meta.name.cmp(&b.manifest.meta.name));
+    Ok(packs)
+}
+
+fn installed_pack(dir: &Path) -> Result<TemplatePack, String> {
+    let manifest = read_manifest(dir)?;
+    let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+    let files = &manifest.files;
+    Ok(TemplatePack {
+        dir: dir.to_string_lossy().to_string(),
+        reference_doc: path(&files.reference_doc),
+        filters: files.filters.iter().map(|f| path(f)).collect(),
+        csl: files.csl.as_deref().map(path),
+        sample: files.sample.as_deref().map(path),
+        preview: files.preview.as_deref().map(path),
+        manifest,
+    })
+}
+
+fn read_manifest(dir: &Path) -> Result<PackManifest, String> {
+    let content = fs::read_to_string(dir.join(MANIFEST_NAME))
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid manifest: {}", e))
+}
+
+/// 读取 zip 中的全部文件，拒绝越出包目录的路径
+fn read_entries(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("Invalid template pack: {}", e))?;
+
+    let mut entries = BTreeMap::new();
+    let mut total = 0u64;
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read template pack entry: {}", e))?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        if file.enclosed_name().is_none() || !is_relative_path(&name) {
+            return Err(format!("Unsafe path in template pack: {}", name));
+        }
+        // 按实际解压的字节计数，不信任 zip 中声明的大小
+        let mut data = Vec::new();
+        file.by_ref()
+            .take(MAX_PACK_SIZE - total + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {} from template pack: {}", name, e))?;
+        total += data.len() as u64;
+        if total > MAX_PACK_SIZE {
+            return Err("Template pack is too large".to_string());
+        }
+        entries.insert(name, data);
+    }
+    Ok(entries)
+}
+
+fn check_pack(entries: &BTreeMap<String, Vec<u8>>) -> PackValidation {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let manifest = match entries.get(MANIFEST_NAME) {
+        Some(data) => match serde_json::from_slice::<PackManifest>(data) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                errors.push(format!("Invalid manifest: {}", e));
+                None
+            }
+        },
+        None => {
+            errors.push(format!("Missing {}", MANIFEST_NAME));
+            None
+        }
+    };
+    let Some(manifest) = manifest else {
+        return PackValidation {
+            manifest: None,
+            errors,
+            warnings,
+        };
+    };
+
+    let meta = &manifest.meta;
+    if !is_pack_id(&meta.id) {
+        errors.push(format!(
+            "Invalid template id '{}': use lowercase letters, digits, '-' and '_'",
+            meta.id
+        ));
+    }
+    if meta.name.trim().is_empty() {
+        errors.push("Template name cannot be empty".to_string());
+    }
+
+    let files = &manifest.files;
+    let mut require = |name: &str, label: &str, extensions: &[&str]| -> Option<Vec<u8>> {
+        if name.is_empty() {
+            errors.push(format!("{} is not set in the manifest", label));
+            return None;
+        }
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        if !extensions.is_empty() && !extensions.contains(&extension.as_str()) {
+            errors.push(format!(
+                "{} must be one of .{}: {}",
+                label,
+                extensions.join(", ."),
+                name
+            ));
+            return None;
+        }
+        match entries.get(name) {
+            Some(data) => Some(data.clone()),
+            None => {
+                errors.push(format!("{} not found in pack: {}", label, name));
+                None
+            }
+        }
+    };
+
+    // 受保护的参考文档不限制扩展名
+    let extensions: &[&str] = if files.encrypted { &[] } else { &["docx"] };
+    let reference_doc = require(&files.reference_doc, "Reference doc", extensions);
+    let preset = files
+        .preset
+        .as_deref()
+        .and_then(|p| require(p, "Preset", &["json"]));
+    for filter in &files.filters {
+        require(filter, "Lua filter", &["lua"]);
+    }
+    let csl = files
+        .csl
+        .as_deref()
+        .and_then(|c| require(c, "CSL style", &["csl"]));
+    if let Some(sample) = &files.sample {
+        require(sample, "Sample document", &["md", "markdown"]);
+    }
+    if let Some(preview) = &files.preview {
+        require(preview, "Preview image", &PREVIEW_EXTENSIONS);
+    }
+
+    if let Some(doc) = reference_doc {
+        if files.encrypted {
+            warnings.push("Protected reference doc is not inspected".to_string());
+        } else {
+            match inspect_docx(&doc) {
+                Ok(report) if !report.missing_pandoc_styles.is_empty() => warnings.push(format!(
+                    "Reference doc is missing pandoc styles: {}",
+                    report.missing_pandoc_styles.join(", ")
+                )),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("Invalid reference doc: {}", e)),
+            }
+        }
+    }
+    if let Some(preset) = preset {
+        if !matches!(
+            serde_json::from_slice::<serde_json::Value>(&preset),
+            Ok(serde_json::Value::Object(_))
+        ) {
+            errors.push("Preset must be a JSON object".to_string());
+        } else if !meta.defaultPreset.is_null() {
+            warnings.push(
+                "Both defaultPreset and a preset file are given; defaultPreset is used".to_string(),
+            );
+        }
+    }
+    if let Some(csl) = csl {
+        if !String::from_utf8_lossy(&csl).contains("<style") {
+            errors.push("CSL file is not a CSL style".to_string());
+        }
+    }
+
+    PackValidation {
+        manifest: Some(manifest),
+        errors,
+        warnings,
+    }
+}
+
+/// 包 ID 同时用作目录名；`user-` 前缀留给导入的模板
+fn is_pack_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with("user-")
+        && id.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn is_relative_path(name: &str) -> bool {
+    Path::new(name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+}
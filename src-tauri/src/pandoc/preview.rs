@@ -0,0 +1,528 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use super::config::get_pandoc_executable_path;
+use super::converter::{convert_md_to_docx, ConvertOptions};
+use super::frontmatter::MetadataPrecedence;
+use super::license::template_key;
+use super::packs::list_packs;
+use super::runtime::write_staged;
+use super::styles::{docx_styles, DocxStyle, RunFonts};
+use super::templates::{bundled_template_meta, read_stored_template, read_template_bytes};
+
+/// 内置示例文档，与参考文献、插图同在 resources/samples
+const SAMPLE_FILE: &str = "preview.md";
+const SAMPLE_BIBLIOGRAPHY: &str = "preview.bib";
+const PREVIEW_DOCX: &str = "preview.docx";
+const PREVIEW_HTML: &str = "preview.html";
+/// 预览生成方式变化时递增，旧缓存随之失效
+const CACHE_VERSION: &str = "1";
+/// 最多保留的预览数量，超出时删除最早生成的
+const MAX_CACHED: usize = 30;
+/// 生成中断后残留的临时目录保留的时间
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// 模板预览：示例文档套用模板与预设后的 DOCX，以及供界面直接显示的 HTML 近似效果
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplatePreview {
+    pub template_id: String,
+    /// 受保护模板的 DOCX 等同于解密后的模板，不缓存，为 None
+    pub docx_path: Option<String>,
+    pub html_path: String,
+    /// 直接使用了已有的缓存
+    pub cached: bool,
+}
+
+/// 生成预览所需的全部输入，缓存 key 按这些内容计算
+struct PreviewSource {
+    /// 模板在磁盘上的原始内容（受保护模板为密文），生成预览时才解密
+    stored_template: Vec<u8>,
+    protected: bool,
+    /// 示例文档所在目录，整体复制到工作目录，保持相对路径的图片可用
+    sample_dir: PathBuf,
+    sample_name: String,
+    bibliography: Option<String>,
+    custom_styles: BTreeMap<String, String>,
+    lua_filters: Vec<String>,
+    csl: Option<String>,
+}
+
+/// 用示例文档生成模板预览，相同模板、预设与示例的结果直接取缓存
+///
+/// `metadata` 为预设对应的 Pandoc 元数据，与正式转换时传入的一致；受保护模板同样需要授权。
+pub async fn generate_preview(
+    app: &AppHandle,
+    template_id: &str,
+    metadata: Option<Value>,
+    use_crossref: bool,
+    refresh: bool,
+) -> Result<TemplatePreview, String> {
+    let source = load_source(app, template_id)?;
+    let key = cache_key(template_id, &source, metadata.as_ref(), use_crossref)?;
+    // 受保护模板命中缓存时同样检查授权
+    if source.protected {
+        template_key(app, template_id)?;
+    }
+
+    let root = previews_dir(app)?;
+    fs::create_dir_all(&root).map_err(|e| format!("Failed to create preview dir: {}", e))?;
+    let target = root.join(format!("{}-{}", file_stem(template_id), &key[..16]));
+
+    let complete = target.join(PREVIEW_HTML).exists()
+        && (source.protected || target.join(PREVIEW_DOCX).exists());
+    if !refresh && complete {
+        return Ok(preview_result(template_id, &target, source.protected, true));
+    }
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_millis();
+    let building = root.join(format!(".{}-{}.tmp", file_stem(template_id), millis));
+    let mut result =
+        build_preview(app, template_id, &source, metadata, use_crossref, &building).await;
+    // 受保护模板只缓存渲染后的 HTML
+    if source.protected && result.is_ok() {
+        result = fs::remove_file(building.join(PREVIEW_DOCX))
+            .map_err(|e| format!("Failed to clean up preview: {}", e));
+    }
+    if let Err(e) = result {
+        let _ = fs::remove_dir_all(&building);
+        return Err(e);
+    }
+
+    // 先在临时目录生成完整结果再换入，界面不会读到一半的预览
+    if target.exists() {
+        fs::remove_dir_all(&target)
+            .map_err(|e| format!("Failed to replace cached preview: {}", e))?;
+    }
+    if let Err(e) = fs::rename(&building, &target) {
+        let _ = fs::remove_dir_all(&building);
+        return Err(format!("Failed to save preview: {}", e));
+    }
+
+    prune_previews(&root, &target);
+    Ok(preview_result(
+        template_id,
+        &target,
+        source.protected,
+        false,
+    ))
+}
+
+/// 删除全部缓存的预览
+pub fn clear_previews(app: &AppHandle) -> Result<(), String> {
+    let root = previews_dir(app)?;
+    if root.exists() {
+        fs::remove_dir_all(&root).map_err(|e| format!("Failed to clear previews: {}", e))?;
+    }
+    Ok(())
+}
+
+fn previews_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let cache_root = app
+        .path()
+        .cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?;
+    Ok(cache_root.join("formatsman").join("previews"))
+}
+
+fn preview_result(template_id: &str, dir: &Path, protected: bool, cached: bool) -> TemplatePreview {
+    TemplatePreview {
+        template_id: template_id.to_string(),
+        docx_path: (!protected).then(|| dir.join(PREVIEW_DOCX).to_string_lossy().to_string()),
+        html_path: dir.join(PREVIEW_HTML).to_string_lossy().to_string(),
+        cached,
+    }
+}
+
+/// 收集参考文档与示例；模板包自带示例时优先使用，并带上包内的过滤器与引文样式
+fn load_source(app: &AppHandle, template_id: &str) -> Result<PreviewSource, String> {
+    let bundled = bundled_template_meta(app, template_id)?;
+    let (stored_template, protected) = read_stored_template(app, template_id)?;
+
+    let pack = list_packs(app)?
+        .into_iter()
+        .find(|p| p.manifest.meta.id == template_id);
+    let custom_styles = match (&bundled, &pack) {
+        (Some(meta), _) => meta.customStyles.clone(),
+        (None, Some(pack)) => pack.manifest.meta.customStyles.clone(),
+        _ => BTreeMap::new(),
+    };
+    let (lua_filters, csl) = pack
+        .as_ref()
+        .map(|p| (p.filters.clone(), p.csl.clone()))
+        .unwrap_or_default();
+
+    if let Some(sample) = pack.as_ref().and_then(|p| p.sample.as_ref()) {
+        let sample = PathBuf::from(sample);
+        if let (Some(dir), Some(name)) = (sample.parent(), sample.file_name()) {
+            return Ok(PreviewSource {
+                stored_template,
+                protected,
+                sample_dir: dir.to_path_buf(),
+                sample_name: name.to_string_lossy().to_string(),
+                bibliography: None,
+                custom_styles,
+                lua_filters,
+                csl,
+            });
+        }
+    }
+
+    let sample_dir = find_bundled_samples(app)
+        .ok_or("Preview sample not found in resources/samples".to_string())?;
+    Ok(PreviewSource {
+        stored_template,
+        protected,
+        sample_dir,
+        sample_name: SAMPLE_FILE.to_string(),
+        bibliography: Some(SAMPLE_BIBLIOGRAPHY.to_string()),
+        custom_styles,
+        lua_filters,
+        csl,
+    })
+}
+
+/// 内置示例目录（resources/samples），查找方式与内置模板相同
+fn find_bundled_samples(app: &AppHandle) -> Option<PathBuf> {
+    let dev_candidates = [
+        PathBuf::from("src-tauri/resources/samples"),
+        PathBuf::from("resources/samples"),
+    ];
+    for dir in &dev_candidates {
+        if dir.join(SAMPLE_FILE).exists() {
+            return Some(dir.clone());
+        }
+    }
+
+    let candidates = [Path::new("samples"), Path::new("resources/samples")];
+    for candidate in &candidates {
+        if let Ok(dir) = app.path().resolve(candidate, BaseDirectory::Resource) {
+            if dir.join(SAMPLE_FILE).exists() {
+                return Some(dir);
+            }
+        }
+    }
+    None
+}
+
+/// 模板内容、示例目录、预设与过滤器共同决定缓存 key，任何一项变化都会重新生成
+fn cache_key(
+    template_id: &str,
+    source: &PreviewSource,
+    metadata: Option<&Value>,
+    use_crossref: bool,
+) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    hasher.update(CACHE_VERSION.as_bytes());
+    hasher.update([0]);
+    hasher.update(template_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(&source.stored_template);
+    hasher.update([0]);
+    hash_dir(&mut hasher, &source.sample_dir, Path::new(""))?;
+    hasher.update(source.sample_name.as_bytes());
+    hasher.update([0]);
+    let metadata = metadata.map(|m| m.to_string()).unwrap_or_default();
+    hasher.update(metadata.as_bytes());
+    hasher.update([0, use_crossref as u8]);
+    for (class, style) in &source.custom_styles {
+        hasher.update(format!("{}={}\n", class, style).as_bytes());
+    }
+    for path in source.lua_filters.iter().chain(source.csl.iter()) {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        hasher.update(&bytes);
+        hasher.update([0]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn hash_dir(hasher: &mut Sha256, dir: &Path, relative: &Path) -> Result<(), String> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read sample dir: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let relative = relative.join(name);
+        if path.is_dir() {
+            hash_dir(hasher, &path, &relative)?;
+        } else {
+            let bytes =
+                fs::read(&path).map_err(|e| format!("Failed to read sample file: {}", e))?;
+            hasher.update(relative.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(&bytes);
+        }
+    }
+    Ok(())
+}
+
+async fn build_preview(
+    app: &AppHandle,
+    template_id: &str,
+    source: &PreviewSource,
+    metadata: Option<Value>,
+    use_crossref: bool,
+    dir: &Path,
+) -> Result<(), String> {
+    // 示例复制到工作目录后再转换，元数据注入不会改动内置示例
+    let work_dir = dir.join("work");
+    copy_dir(&source.sample_dir, &work_dir)?;
+
+    // 参考文档放入 runtime 目录，预览生成结束时删除
+    let template = read_template_bytes(app, template_id)?;
+    let reference_doc = write_staged(app, template_id, &template)?;
+
+    let options = ConvertOptions {
+        input_file: work_dir
+            .join(&source.sample_name)
+            .to_string_lossy()
+            .to_string(),
+        output_file: Some(dir.join(PREVIEW_DOCX).to_string_lossy().to_string()),
+        source_dir: None,
+        source_name: None,
+        reference_doc: Some(reference_doc.path().to_string_lossy().to_string()),
+        template_id: None,
+        metadata,
+        metadata_file: None,
+        use_crossref,
+        bibliography: source
+            .bibliography
+            .as_ref()
+            .map(|bib| vec![work_dir.join(bib).to_string_lossy().to_string()]),
+        chinese_conversion: None,
+        metadata_precedence: MetadataPrecedence::default(),
+        custom_styles: Some(source.custom_styles.clone()),
+        style_overrides: None,
+        lua_filters: Some(source.lua_filters.clone()),
+        csl: source.csl.clone(),
+    };
+    // 转换器会给输出文件名加后缀，生成后统一改回固定文件名
+    let output = PathBuf::from(convert_md_to_docx(app, options).await?);
+    let docx = dir.join(PREVIEW_DOCX);
+    if output != docx {
+        fs::rename(&output, &docx).map_err(|e| format!("Failed to save preview: {}", e))?;
+    }
+
+    let css = preview_css(&docx_styles(&template).unwrap_or_else(|e| {
+        log::warn!("Failed to read styles for preview: {}", e);
+        Vec::new()
+    }));
+    render_html(
+        app,
+        template_id,
+        &docx,
+        &dir.join(PREVIEW_HTML),
+        &work_dir,
+        &css,
+    )?;
+
+    fs::remove_dir_all(&work_dir).map_err(|e| format!("Failed to clean up preview: {}", e))
+}
+
+/// 把生成的 DOCX 转为独立的 HTML 文件，图片内嵌，公式用 MathML
+fn render_html(
+    app: &AppHandle,
+    title: &str,
+    docx: &Path,
+    output: &Path,
+    work_dir: &Path,
+    css: &str,
+) -> Result<(), String> {
+    let pandoc_exe = get_pandoc_executable_path(app)?;
+    let header = work_dir.join("preview-style.html");
+    fs::write(&header, format!("<style>\n{}</style>\n", css))
+        .map_err(|e| format!("Failed to write preview style: {}", e))?;
+
+    let mut cmd = Command::new(&pandoc_exe);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    cmd.arg(docx)
+        .arg("-f")
+        .arg("docx")
+        .arg("-t")
+        .arg("html5")
+        .arg("--standalone")
+        .arg("--embed-resources")
+        .arg("--mathml")
+        .arg("--include-in-header")
+        .arg(&header)
+        .arg("--metadata")
+        .arg(format!("pagetitle={}", title))
+        .arg("-o")
+        .arg(output)
+        .current_dir(work_dir);
+
+    let result = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute pandoc: {}", e))?;
+    if result.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Pandoc preview rendering failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        ))
+    }
+}
+
+/// 页面的基础样式，模板中的字体与字号按样式名称覆盖在后面
+const BASE_CSS: &str =
+    "body { max-width: 160mm; margin: 2em auto; padding: 0 1.5em; line-height: 1.6; color: #222; }
+img { max-width: 100%; }
+table { border-collapse: collapse; margin: 1em auto; }
+th, td { border-top: 1px solid #999; border-bottom: 1px solid #999; padding: 0.3em 0.8em; }
+figure { text-align: center; }
+blockquote { margin-left: 2em; color: #555; }
+pre { background: #f6f6f6; padding: 0.6em; overflow-x: auto; }
+";
+
+/// 按参考文档中的段落样式近似出 CSS，只覆盖字体与字号
+fn preview_css(styles: &[DocxStyle]) -> String {
+    let mut css = String::from(BASE_CSS);
+    for style in styles {
+        let Some(selector) = css_selector(&style.name) else {
+            continue;
+        };
+        let mut rules = Vec::new();
+        if let Some(families) = font_families(&style.fonts) {
+            rules.push(format!("font-family: {}", families));
+        }
+        if let Some(size) = style.size_pt {
+            rules.push(format!("font-size: {}pt", size));
+        }
+        if !rules.is_empty() {
+            css.push_str(&format!("{} {{ {}; }}\n", selector, rules.join("; ")));
+        }
+    }
+    css
+}
+
+/// Word 样式名称对应的 HTML 元素，与 pandoc 读取 DOCX 时的结构一致
+fn css_selector(style_name: &str) -> Option<String> {
+    let name = style_name.to_ascii_lowercase();
+    if let Some(level) = name
+        .strip_prefix("heading ")
+        .and_then(|n| n.parse::<u8>().ok())
+        .filter(|n| (1..=6).contains(n))
+    {
+        return Some(format!("h{}", level));
+    }
+    let selector = match name.as_str() {
+        "normal" => "body",
+        "body text" | "first paragraph" => "p",
+        "title" => "h1.title",
+        "subtitle" => "p.subtitle",
+        "author" => "p.author",
+        "date" => "p.date",
+        "abstract" => "div.abstract",
+        "block text" => "blockquote",
+        "image caption" => "figcaption",
+        "table caption" => "table caption",
+        "source code" => "pre, code",
+        _ => return None,
+    };
+    Some(selector.to_string())
+}
+
+/// 西文字体在前、中文字体在后；主题字体（`+minorHAnsi` 等）无法在页面中使用，跳过
+fn font_families(fonts: &RunFonts) -> Option<String> {
+    let mut families: Vec<&str> = Vec::new();
+    for font in [&fonts.ascii, &fonts.h_ansi, &fonts.east_asia]
+        .into_iter()
+        .flatten()
+    {
+        if !font.starts_with('+') && !families.contains(&font.as_str()) {
+            families.push(font);
+        }
+    }
+    if families.is_empty() {
+        return None;
+    }
+    let mut list: Vec<String> = families
+        .iter()
+        .map(|f| format!("\"{}\"", f.replace(['"', '\\', '<', '>'], "")))
+        .collect();
+    list.push("serif".to_string());
+    Some(list.join(", "))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create preview dir: {}", e))?;
+    let entries = fs::read_dir(from).map_err(|e| format!("Failed to read sample dir: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target).map_err(|e| format!("Failed to copy sample: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 模板 ID 只用作目录名前缀，其余字符一律替换
+fn file_stem(template_id: &str) -> String {
+    template_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 只保留最近生成的预览；中断残留的临时目录超过一定时间后删除
+fn prune_previews(root: &Path, keep: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let now = SystemTime::now();
+    let mut previews: Vec<(PathBuf, SystemTime)> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+            continue;
+        };
+        let temporary = entry.file_name().to_string_lossy().starts_with('.');
+        if temporary {
+            let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+            if age >= STALE_AFTER {
+                let _ = fs::remove_dir_all(&path);
+            }
+        } else if path.is_dir() && path != keep {
+            previews.push((path, modified));
+        }
+    }
+
+    // 最新的在前；刚生成的预览不参与排序，始终保留
+    previews.sort_by(|a, b| b.1.cmp(&a.1));
+    for (path, _) in previews.iter().skip(MAX_CACHED.saturating_sub(1)) {
+        if let Err(e) = fs::remove_dir_all(path) {
+            log::warn!("Failed to remove old preview {}: {}", path.display(), e);
+        }
+    }
+}
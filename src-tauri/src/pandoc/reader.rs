@@ -0,0 +1,100 @@
+use serde_yaml::{Mapping as YamlMapping, Value as YamlValue};
+use std::path::Path;
+use std::process::Command;
+
+/// pandoc 输出的 Markdown 方言：去掉原始 HTML 与 div/span 包装，便于后续处理
+const MARKDOWN_WRITER: &str = "markdown-raw_html-native_divs-native_spans";
+
+/// 用 pandoc 把其他格式读成 Markdown 文本
+///
+/// `cwd` 决定相对资源路径的解析位置，`extra_args` 追加在输入文件之前。
+pub fn pandoc_to_markdown(
+    pandoc_exe: Option<&Path>,
+    input: &Path,
+    from: &str,
+    extra_args: &[String],
+    cwd: Option<&Path>,
+) -> Result<String, String> {
+    let pandoc_exe = pandoc_exe
+        .filter(|p| p.exists())
+        .ok_or_else(|| "Pandoc not installed. Please install it first.".to_string())?;
+
+    let mut cmd = Command::new(pandoc_exe);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    cmd.arg("-f")
+        .arg(from)
+        .arg("-t")
+        .arg(MARKDOWN_WRITER)
+        .arg("--wrap=none")
+        .args(extra_args)
+        .arg(input);
+
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute pandoc: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let error = String::from_utf8_lossy(&output.stderr);
+        Err(format!("Pandoc failed to read {}: {}", from, error))
+    }
+}
+
+/// 读取自带图片的打包文档（ODT 等）：pandoc 把图片解压到 `media_dir`，
+/// Markdown 中以绝对路径引用，随后由图片复制流程收入 `assets/`。
+pub fn packaged_to_markdown(
+    pandoc_exe: Option<&Path>,
+    input: &Path,
+    from: &str,
+    media_dir: &Path,
+) -> Result<String, String> {
+    let extract_media = format!("--extract-media={}", media_dir.to_string_lossy());
+    pandoc_to_markdown(
+        pandoc_exe,
+        input,
+        from,
+        &["-s".to_string(), extract_media],
+        input.parent(),
+    )
+}
+
+/// 生成只含 title/author 的 YAML front matter
+pub fn title_author_front_matter(
+    title: Option<&str>,
+    authors: &[String],
+) -> Result<Option<String>, String> {
+    let mut map = YamlMapping::new();
+
+    if let Some(title) = title.map(str::trim).filter(|t| !t.is_empty()) {
+        map.insert("title".into(), title.into());
+    }
+
+    match authors {
+        [] => {}
+        [single] => {
+            map.insert("author".into(), single.clone().into());
+        }
+        many => {
+            let list = many.iter().cloned().map(YamlValue::from).collect();
+            map.insert("author".into(), YamlValue::Sequence(list));
+        }
+    }
+
+    if map.is_empty() {
+        return Ok(None);
+    }
+    let yaml = serde_yaml::to_string(&YamlValue::Mapping(map))
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    Ok(Some(format!("---\n{}---", yaml)))
+}
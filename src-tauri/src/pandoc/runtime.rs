@@ -0,0 +1,162 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// runtime 目录中的一个临时模板，离开作用域时删除
+///
+/// 转换中途出错、pandoc 失败或提前返回时都会清理；需要交给前端继续使用时调用 `keep`。
+#[derive(Debug)]
+pub struct StagedFile {
+    path: PathBuf,
+    armed: bool,
+}
+
+impl StagedFile {
+    /// 接管已存在的 runtime 文件；不在 runtime 目录中的路径返回 None，避免误删模板原文件
+    pub fn adopt(app: &AppHandle, path: &Path) -> Option<Self> {
+        is_runtime_path(app, path).then(|| Self {
+            path: path.to_path_buf(),
+            armed: true,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 不再自动删除，返回路径；之后由转换结束或下次启动时的清理负责删除
+    ///
+    /// 只用于不含受保护内容的文件，解密后的模板应一直由 guard 持有。
+    pub fn keep(mut self) -> PathBuf {
+        self.armed = false;
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if self.armed && self.path.exists() {
+            if let Err(e) = fs::remove_file(&self.path) {
+                log::warn!(
+                    "Failed to remove staged template {}: {}",
+                    self.path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+pub fn runtime_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let cache_root = app
+        .path()
+        .cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?;
+
+    Ok(cache_root
+        .join("formatsman")
+        .join("templates")
+        .join("runtime"))
+}
+
+pub fn is_runtime_path(app: &AppHandle, path: &Path) -> bool {
+    let Ok(dir) = runtime_dir(app) else {
+        return false;
+    };
+    // 比较规范化后的路径，`..` 等写法不能逃出 runtime 目录
+    match (dir.canonicalize(), path.canonicalize()) {
+        (Ok(dir), Ok(path)) => path.starts_with(dir),
+        _ => false,
+    }
+}
+
+/// 把模板内容写入 runtime 目录，文件只对当前用户可读写
+pub fn write_staged(app: &AppHandle, name: &str, bytes: &[u8]) -> Result<StagedFile, String> {
+    let dir = runtime_dir(app)?;
+    create_private_dir(&dir)?;
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_millis();
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    // 模板 ID 只用作文件名前缀，其余字符一律替换
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let path = dir.join(format!("{}-{}-{}.docx", name, millis, sequence));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    // 先交给 guard，写入失败时不留下半个文件
+    let staged = StagedFile { path, armed: true };
+    let mut file = options
+        .open(staged.path())
+        .map_err(|e| format!("Failed to stage template: {}", e))?;
+    file.write_all(bytes)
+        .map_err(|e| format!("Failed to stage template: {}", e))?;
+    Ok(staged)
+}
+
+/// 覆盖写入已接管的 runtime 文件，保持原有权限
+pub fn rewrite_staged(staged: &StagedFile, bytes: &[u8]) -> Result<(), String> {
+    fs::write(staged.path(), bytes).map_err(|e| format!("Failed to stage template: {}", e))
+}
+
+/// 启动时清空 runtime 目录：上次运行异常退出时残留的模板都在这里
+pub fn sweep_runtime(app: &AppHandle) {
+    match runtime_dir(app) {
+        Ok(dir) if dir.exists() => sweep_stale(&dir, Duration::ZERO),
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to locate runtime templates: {}", e),
+    }
+}
+
+fn sweep_stale(dir: &Path, older_than: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let age = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or(Duration::ZERO);
+        if path.is_file() && age >= older_than {
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove stale template {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create runtime dir: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to secure runtime dir: {}", e))?;
+    }
+    Ok(())
+}
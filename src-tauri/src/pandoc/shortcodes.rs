@@ -0,0 +1,347 @@
+use regex::{Captures, Regex};
+use std::path::Path;
+
+use super::attrs::{tokenize, unquote, Attributes};
+use super::cjk::{inline_code_spans, map_prose};
+
+/// VuePress/VitePress 容器与各主题提示块常用的类型
+const CONTAINER_KINDS: &str = "tip|note|info|warning|danger|caution|important|details";
+
+/// 把 Hexo 标签插件、Hugo shortcode 与 VuePress 容器翻译成 pandoc 能识别的 Markdown
+///
+/// 图片转为 `![]()`，代码块转为围栏代码，提示块统一为 `::: {.tip title="…"}` 形式的 fenced div。
+/// `source_path` 用于定位 Hexo 的文章资源目录（与文章同名的文件夹）。
+pub fn translate_site_syntax(content: &str, source_path: Option<&Path>) -> Result<String, String> {
+    if !content.contains("{%") && !content.contains("{{") && !content.contains(":::") {
+        return Ok(content.to_string());
+    }
+
+    let translator = SiteSyntax::new()?;
+    let asset_dir = source_path
+        .and_then(|p| p.file_stem())
+        .map(|s| s.to_string_lossy().to_string());
+
+    Ok(map_prose(content, |text| {
+        // 行内代码中展示的 shortcode（介绍 Hugo/Hexo 的文档）保持原样：
+        // 先替换为占位符再整段翻译，跨行的标签块不会被行内代码截断
+        let (masked, spans) = mask_inline_code(text);
+        let translated = translator.translate(&masked, asset_dir.as_deref());
+        unmask_inline_code(&translated, &spans)
+    }))
+}
+
+/// 行内代码的占位符，使用私用区字符，不会与正文或标签语法冲突
+fn code_placeholder(index: usize) -> String {
+    format!("\u{F8F0}{}\u{F8F1}", index)
+}
+
+fn mask_inline_code(text: &str) -> (String, Vec<&str>) {
+    let mut masked = String::with_capacity(text.len());
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for span in inline_code_spans(text) {
+        masked.push_str(&text[last..span.start]);
+        masked.push_str(&code_placeholder(spans.len()));
+        spans.push(&text[span.clone()]);
+        last = span.end;
+    }
+    masked.push_str(&text[last..]);
+    (masked, spans)
+}
+
+fn unmask_inline_code(text: &str, spans: &[&str]) -> String {
+    let mut text = text.to_string();
+    for (index, span) in spans.iter().enumerate() {
+        text = text.replacen(&code_placeholder(index), span, 1);
+    }
+    text
+}
+
+/// 提示块的统一写法
+///
+/// 属性值不支持反斜杠转义：标题含双引号时改用单引号包裹，两种引号都有时双引号改为单引号。
+pub(crate) fn admonition_open(kind: &str, title: Option<&str>) -> String {
+    match title.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(title) => {
+            let title = if title.contains('\'') {
+                title.replace('"', "'")
+            } else {
+                title.to_string()
+            };
+            let quote = if title.contains('"') { '\'' } else { '"' };
+            format!(
+                "::: {{.{} title={q}{}{q}}}",
+                kind.to_lowercase(),
+                title,
+                q = quote
+            )
+        }
+        None => format!("::: {{.{}}}", kind.to_lowercase()),
+    }
+}
+
+struct SiteSyntax {
+    hexo_asset_img: Regex,
+    hexo_img: Regex,
+    hexo_codeblock: Regex,
+    hexo_blockquote: Regex,
+    hexo_note: Regex,
+    hugo_figure: Regex,
+    hugo_highlight: Regex,
+    hugo_ref: Regex,
+    hugo_youtube: Regex,
+    hugo_notice: Regex,
+    container: Regex,
+}
+
+impl SiteSyntax {
+    fn new() -> Result<Self, String> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e))
+        };
+        Ok(Self {
+            hexo_asset_img: compile(r"\{%\s*asset_img\s+(?P<args>.*?)\s*%\}")?,
+            hexo_img: compile(r"\{%\s*img\s+(?P<args>.*?)\s*%\}")?,
+            hexo_codeblock: compile(
+                r"(?s)\{%\s*(?:codeblock|code)\b(?P<args>[^%]*)%\}\n?(?P<body>.*?)\n?\{%\s*end(?:codeblock|code)\s*%\}",
+            )?,
+            hexo_blockquote: compile(
+                r"(?s)\{%\s*(?:blockquote|quote)\b(?P<args>[^%]*)%\}\n?(?P<body>.*?)\n?\{%\s*end(?:blockquote|quote)\s*%\}",
+            )?,
+            hexo_note: compile(
+                r"(?s)\{%\s*note\b(?P<args>[^%]*)%\}\n?(?P<body>.*?)\n?\{%\s*endnote\s*%\}",
+            )?,
+            hugo_figure: compile(r"\{\{[<%]\s*figure\s+(?P<args>.*?)\s*/?[>%]\}\}")?,
+            hugo_highlight: compile(
+                r"(?s)\{\{<\s*highlight\s+(?P<lang>[\w+#.-]+)[^>]*>\}\}\n?(?P<body>.*?)\n?\{\{<\s*/highlight\s*>\}\}",
+            )?,
+            hugo_ref: compile(r#"\{\{[<%]\s*(?:rel)?ref\s+"?(?P<path>[^"\s>%]+)"?\s*[>%]\}\}"#)?,
+            hugo_youtube: compile(r#"\{\{<\s*youtube\s+(?:id=)?"?(?P<id>[\w-]+)"?\s*>\}\}"#)?,
+            hugo_notice: compile(
+                r"(?s)\{\{%\s*notice\s+(?P<kind>\w+)(?P<title>[^%]*)%\}\}\n?(?P<body>.*?)\n?\{\{%\s*/notice\s*%\}\}",
+            )?,
+            container: compile(&format!(
+                r"(?m)^(?P<indent> {{0,3}}):::\s*(?P<kind>{})(?:[ \t]+(?P<title>[^\n]*?))?[ \t]*$",
+                CONTAINER_KINDS
+            ))?,
+        })
+    }
+
+    fn translate(&self, text: &str, asset_dir: Option<&str>) -> String {
+        let text = self.hexo_codeblock.replace_all(text, |caps: &Captures| {
+            let lang = tokenize(&caps["args"])
+                .iter()
+                .find_map(|t| t.strip_prefix("lang:").map(String::from))
+                .unwrap_or_default();
+            fenced_code(&lang, &caps["body"])
+        });
+        let text = self.hugo_highlight.replace_all(&text, |caps: &Captures| {
+            fenced_code(&caps["lang"], &caps["body"])
+        });
+
+        let text = self.hexo_blockquote.replace_all(&text, |caps: &Captures| {
+            let mut quote = quote_lines(caps["body"].trim());
+            let source = caps["args"].trim();
+            if !source.is_empty() {
+                quote.push_str(&format!("\n>\n> —— {}", source));
+            }
+            quote
+        });
+        let text = self.hexo_note.replace_all(&text, |caps: &Captures| {
+            let args = tokenize(&caps["args"]);
+            let kind = args.first().map(|k| unquote(k)).unwrap_or("note");
+            format!(
+                "{}\n{}\n:::",
+                admonition_open(kind, None),
+                caps["body"].trim_end()
+            )
+        });
+        let text = self.hugo_notice.replace_all(&text, |caps: &Captures| {
+            let title = unquote(caps["title"].trim());
+            format!(
+                "{}\n{}\n:::",
+                admonition_open(&caps["kind"], Some(title)),
+                caps["body"].trim_end()
+            )
+        });
+
+        let text = self.hexo_asset_img.replace_all(&text, |caps: &Captures| {
+            let args: Vec<String> = tokenize(&caps["args"])
+                .iter()
+                .map(|t| unquote(t).to_string())
+                .collect();
+            let Some(slug) = args.first() else {
+                return caps[0].to_string();
+            };
+            // Hexo 开启 post_asset_folder 后，图片位于与文章同名的目录
+            let path = match asset_dir {
+                Some(dir) => format!("{}/{}", dir, slug),
+                None => slug.clone(),
+            };
+            image(&args[1..].join(" "), &path, None)
+        });
+        let text = self.hexo_img.replace_all(&text, |caps: &Captures| {
+            hexo_img(&caps["args"]).unwrap_or_else(|| caps[0].to_string())
+        });
+
+        let text = self.hugo_figure.replace_all(&text, |caps: &Captures| {
+            let attrs = Attributes::parse(&caps["args"]);
+            let Some(src) = attrs.get("src") else {
+                return caps[0].to_string();
+            };
+            let caption = ["caption", "title", "alt"]
+                .iter()
+                .find_map(|k| attrs.get(k))
+                .unwrap_or("");
+            image(caption, src, attrs.get("width"))
+        });
+        let text = self.hugo_ref.replace_all(&text, "$path");
+        let text = self.hugo_youtube.replace_all(&text, |caps: &Captures| {
+            let url = format!("https://www.youtube.com/watch?v={}", &caps["id"]);
+            format!("[{}]({})", url, url)
+        });
+
+        self.container
+            .replace_all(&text, |caps: &Captures| {
+                let title = caps.name("title").map(|m| m.as_str());
+                format!(
+                    "{}{}",
+                    &caps["indent"],
+                    admonition_open(&caps["kind"], title)
+                )
+            })
+            .into_owned()
+    }
+}
+
+/// `{% img [class names] /path/to/image [width] [height] '"title text" "alt text"' %}`
+fn hexo_img(args: &str) -> Option<String> {
+    let tokens = tokenize(args);
+    let src_idx = tokens.iter().position(|t| {
+        let t = unquote(t);
+        t.contains('/') || t.contains("://") || t.contains('.')
+    })?;
+    let src = unquote(&tokens[src_idx]).to_string();
+
+    let rest = &tokens[src_idx + 1..];
+    let width = rest
+        .first()
+        .filter(|w| w.chars().all(|c| c.is_ascii_digit()))
+        .cloned();
+    let text_tokens: Vec<&str> = rest
+        .iter()
+        .skip_while(|t| t.chars().all(|c| c.is_ascii_digit()))
+        .map(|t| unquote(t))
+        .collect();
+
+    // 单个引号参数里再包含 "title" "alt" 两段
+    let joined = text_tokens.join(" ");
+    let parts: Vec<String> = tokenize(&joined)
+        .iter()
+        .map(|t| unquote(t).to_string())
+        .collect();
+    let alt = parts.get(1).or(parts.first()).cloned().unwrap_or_default();
+
+    Some(image(&alt, &src, width.as_deref()))
+}
+
+fn image(alt: &str, path: &str, width: Option<&str>) -> String {
+    let target = if path.contains(' ') {
+        format!("<{}>", path)
+    } else {
+        path.to_string()
+    };
+    let attrs = match width {
+        Some(w) if w.chars().all(|c| c.is_ascii_digit()) => format!("{{width={}px}}", w),
+        Some(w) => format!("{{width={}}}", w),
+        None => String::new(),
+    };
+    format!("![{}]({}){}", alt.trim(), target, attrs)
+}
+
+fn fenced_code(lang: &str, body: &str) -> String {
+    let fence = if body.contains("```") { "````" } else { "```" };
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        lang.trim(),
+        body.trim_end_matches('\n'),
+        fence
+    )
+}
+
+fn quote_lines(body: &str) -> String {
+    body.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(content: &str) -> String {
+        translate_site_syntax(content, Some(Path::new("posts/hello.md"))).unwrap()
+    }
+
+    #[test]
+    fn shortcodes_inside_inline_code_are_untouched() {
+        let text = "用 `{% asset_img a.png 图 %}` 插图：\n\n{% asset_img a.png 图 %}\n";
+        assert_eq!(
+            translate(text),
+            "用 `{% asset_img a.png 图 %}` 插图：\n\n![图](hello/a.png)\n"
+        );
+        let hugo = "``{{< youtube abc >}}`` 与 {{< youtube abc >}}\n";
+        assert_eq!(
+            translate(hugo),
+            "``{{< youtube abc >}}`` 与 [https://www.youtube.com/watch?v=abc](https://www.youtube.com/watch?v=abc)\n"
+        );
+    }
+
+    #[test]
+    fn multi_line_tags_and_fenced_code() {
+        let text =
+            "{% codeblock lang:rust %}\nfn main() {}\n{% endcodeblock %}\n\n```\n{% note %}\n```\n";
+        assert_eq!(
+            translate(text),
+            "```rust\nfn main() {}\n```\n\n```\n{% note %}\n```\n"
+        );
+    }
+
+    #[test]
+    fn notice_titles_with_quotes_can_be_parsed_back() {
+        for title in ["普通标题", "say \"hi\"", "it's \"ok\"", "it's"] {
+            let open = admonition_open("Warning", Some(title));
+            let attrs = Attributes::parse(open.trim_start_matches(':'));
+            assert!(attrs.has_class("warning"), "{}", open);
+            let expected = if title.contains('\'') {
+                title.replace('"', "'")
+            } else {
+                title.to_string()
+            };
+            assert_eq!(attrs.get("title"), Some(expected.as_str()), "{}", open);
+        }
+
+        let translated = translate("{{% notice tip 'say \"hi\"' %}}\n内容\n{{% /notice %}}\n");
+        assert_eq!(translated, "::: {.tip title='say \"hi\"'}\n内容\n:::\n");
+    }
+
+    #[test]
+    fn containers_and_figures() {
+        assert_eq!(
+            translate("::: warning 小心\n内容\n:::\n"),
+            "::: {.warning title=\"小心\"}\n内容\n:::\n"
+        );
+        assert_eq!(
+            translate("{{< figure src=\"a b.png\" caption=\"图一\" width=\"50%\" >}}\n"),
+            "![图一](<a b.png>){width=50%}\n"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use super::attrs::Attributes;
+use super::cjk::{map_outside_code, map_prose};
+use super::styles::{DocxStyle, StyleKind};
+
+/// 映射中无法使用的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomStyleIssue {
+    pub class: String,
+    pub style: String,
+    pub message: String,
+}
+
+/// 按参考文档校验后的 class → Word 样式映射
+///
+/// 段落样式作用于 fenced div（`::: abstract`），字符样式作用于 span（`[文字]{.lead}`）。
+#[derive(Debug, Clone, Default)]
+pub struct CustomStyles {
+    paragraph: HashMap<String, String>,
+    character: HashMap<String, String>,
+}
+
+impl CustomStyles {
+    /// 校验模板定义的映射，返回可用部分与问题列表
+    ///
+    /// 样式按名称匹配（不区分大小写），也接受样式 ID，写出时统一使用模板中的样式名。
+    pub fn resolve(
+        mapping: &BTreeMap<String, String>,
+        styles: &[DocxStyle],
+    ) -> (Self, Vec<CustomStyleIssue>) {
+        let mut resolved = Self::default();
+        let mut issues = Vec::new();
+
+        for (class, style_name) in mapping {
+            let issue = |message: &str| CustomStyleIssue {
+                class: class.clone(),
+                style: style_name.clone(),
+                message: message.to_string(),
+            };
+
+            let key = class.trim().trim_start_matches('.').to_lowercase();
+            if key.is_empty() || key.contains(|c: char| c.is_whitespace() || "{}#.=".contains(c)) {
+                issues.push(issue("Invalid class name"));
+                continue;
+            }
+
+            let target = style_name.trim();
+            let style = styles
+                .iter()
+                .find(|s| s.name.eq_ignore_ascii_case(target))
+                .or_else(|| styles.iter().find(|s| s.id.eq_ignore_ascii_case(target)));
+            match style {
+                None => issues.push(issue("Style is not defined in the reference doc")),
+                Some(style) => match style.kind {
+                    StyleKind::Paragraph => {
+                        resolved.paragraph.insert(key, style.name.clone());
+                    }
+                    StyleKind::Character => {
+                        resolved.character.insert(key, style.name.clone());
+                    }
+                    StyleKind::Table | StyleKind::Numbering => issues.push(issue(
+                        "Only paragraph and character styles can be used as custom-style",
+                    )),
+                },
+            }
+        }
+        (resolved, issues)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paragraph.is_empty() && self.character.is_empty()
+    }
+
+    fn paragraph_style(&self, attrs: &Attributes) -> Option<&String> {
+        attrs
+            .classes
+            .iter()
+            .find_map(|c| self.paragraph.get(&c.to_lowercase()))
+    }
+
+    fn character_style(&self, attrs: &Attributes) -> Option<&String> {
+        attrs
+            .classes
+            .iter()
+            .find_map(|c| self.character.get(&c.to_lowercase()))
+    }
+}
+
+/// 为映射到模板样式的 div 与 span 加上 `custom-style` 属性
+///
+/// 已写明 `custom-style` 的元素保持不变，代码块与行内代码不处理。
+pub fn apply_custom_styles(content: &str, styles: &CustomStyles) -> Result<String, String> {
+    if styles.is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let compile =
+        |pattern: &str| Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e));
+    let div_open = compile(
+        r"^(?P<fence> {0,3}:{3,})[ \t]*(?:\{(?P<attrs>[^}]*)\}|(?P<class>[\w-]+)(?:[ \t]+(?P<title>.*?))?)[ \t]*$",
+    )?;
+    let span = compile(r"\]\{(?P<attrs>[^{}\n]*)\}")?;
+
+    Ok(map_prose(content, |text| {
+        text.split('\n')
+            .map(|line| match div_open.captures(line) {
+                Some(caps) => styled_div(&caps, styles).unwrap_or_else(|| line.to_string()),
+                None => map_outside_code(line, |segment| {
+                    span.replace_all(segment, |caps: &Captures| {
+                        let mut attrs = Attributes::parse(&caps["attrs"]);
+                        match styles.character_style(&attrs) {
+                            Some(style) if attrs.get("custom-style").is_none() => {
+                                attrs
+                                    .values
+                                    .insert("custom-style".to_string(), style.clone());
+                                format!("]{}", attrs.render())
+                            }
+                            _ => caps[0].to_string(),
+                        }
+                    })
+                    .into_owned()
+                }),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }))
+}
+
+fn styled_div(caps: &Captures, styles: &CustomStyles) -> Option<String> {
+    let mut attrs = match caps.name("attrs") {
+        Some(attrs) => Attributes::parse(attrs.as_str()),
+        None => {
+            // `::: abstract 标题` 的简写形式
+            let mut attrs = Attributes::default();
+            attrs.classes.push(caps["class"].to_string());
+            if let Some(title) = caps.name("title").filter(|t| !t.as_str().is_empty()) {
+                attrs
+                    .values
+                    .insert("title".to_string(), title.as_str().to_string());
+            }
+            attrs
+        }
+    };
+    if attrs.get("custom-style").is_some() {
+        return None;
+    }
+
+    let style = styles.paragraph_style(&attrs)?.clone();
+    attrs.values.insert("custom-style".to_string(), style);
+    Some(format!("{} {}", &caps["fence"], attrs.render()))
+}
@@ -0,0 +1,189 @@
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Word 样式的类型（`w:type`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StyleKind {
+    Paragraph,
+    Character,
+    Table,
+    Numbering,
+}
+
+/// 字体设置（`w:rFonts`），主题字体以 `+minorHAnsi` 形式记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunFonts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ascii: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub east_asia: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h_ansi: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cs: Option<String>,
+}
+
+impl RunFonts {
+    /// 从包含 `<w:rFonts>` 的 XML 片段读取
+    pub(crate) fn parse(xml: &str) -> Self {
+        let Some(tag) = xml_element(xml, "w:rFonts") else {
+            return Self::default();
+        };
+        let slot = |name: &str| {
+            xml_attr(tag, name).or_else(|| {
+                xml_attr(tag, &format!("{}Theme", name)).map(|theme| format!("+{}", theme))
+            })
+        };
+        Self {
+            ascii: slot("w:ascii"),
+            east_asia: slot("w:eastAsia"),
+            h_ansi: slot("w:hAnsi"),
+            cs: slot("w:cs"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// styles.xml 中定义的一个样式
+#[derive(Debug, Clone, Serialize)]
+pub struct DocxStyle {
+    pub id: String,
+    /// 界面中显示的名称，pandoc 的 custom-style 按名称匹配
+    pub name: String,
+    pub kind: StyleKind,
+    pub based_on: Option<String>,
+    /// 用户自定义样式（非 Word 内置）
+    pub custom: bool,
+    #[serde(skip_serializing_if = "RunFonts::is_empty")]
+    pub fonts: RunFonts,
+    /// 字号（磅）
+    pub size_pt: Option<f32>,
+}
+
+/// 读取 .docx 文件中定义的样式
+pub fn read_docx_styles(path: &Path) -> Result<Vec<DocxStyle>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read reference doc: {}", e))?;
+    docx_styles(&bytes)
+}
+
+/// 从内存中的 .docx 读取样式（解密后的模板不落盘）
+pub fn docx_styles(bytes: &[u8]) -> Result<Vec<DocxStyle>, String> {
+    let xml = read_docx_part(bytes, "word/styles.xml")?
+        .ok_or_else(|| "Reference doc has no styles.xml".to_string())?;
+    parse_styles(&xml)
+}
+
+/// 读取 .docx 中的一个部件，不存在时返回 None
+pub fn read_docx_part(bytes: &[u8], name: &str) -> Result<Option<String>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("Invalid docx file: {}", e))?;
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {} from docx: {}", name, e)),
+    };
+    let mut xml = String::new();
+    file.read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read {} from docx: {}", name, e))?;
+    Ok(Some(xml))
+}
+
+fn parse_styles(xml: &str) -> Result<Vec<DocxStyle>, String> {
+    let style_regex = Regex::new(r"(?s)<w:style\b([^>]*?)(?:/>|>(.*?)</w:style>)")
+        .map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    let mut styles = Vec::new();
+    for caps in style_regex.captures_iter(xml) {
+        let attrs = &caps[1];
+        let body = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+
+        let kind = match xml_attr(attrs, "w:type").as_deref() {
+            Some("character") => StyleKind::Character,
+            Some("table") => StyleKind::Table,
+            Some("numbering") => StyleKind::Numbering,
+            _ => StyleKind::Paragraph,
+        };
+        let id = xml_attr(attrs, "w:styleId").unwrap_or_default();
+        let name = xml_element(body, "w:name")
+            .and_then(|tag| xml_attr(tag, "w:val"))
+            .unwrap_or_else(|| id.clone());
+        let custom = matches!(
+            xml_attr(attrs, "w:customStyle").as_deref(),
+            Some("1" | "true")
+        );
+        let based_on = xml_element(body, "w:basedOn").and_then(|tag| xml_attr(tag, "w:val"));
+
+        // 表格样式的条件格式（w:tblStylePr）里也有 rPr，只取样式自身的设置
+        let own = body.split("<w:tblStylePr").next().unwrap_or(body);
+        let size_pt = xml_element(own, "w:sz")
+            .and_then(|tag| xml_attr(tag, "w:val"))
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|half_points| half_points / 2.0);
+
+        styles.push(DocxStyle {
+            id,
+            name,
+            kind,
+            based_on,
+            custom,
+            fonts: RunFonts::parse(own),
+            size_pt,
+        });
+    }
+    Ok(styles)
+}
+
+/// 片段中第一个 `<tag ...>` 起始标签（含属性）
+pub(crate) fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}", tag);
+    let mut from = 0;
+    while let Some(pos) = xml[from..].find(&open) {
+        let start = from + pos;
+        let rest = &xml[start + open.len()..];
+        if rest.starts_with([' ', '/', '>', '\t', '\r', '\n']) {
+            let end = rest.find('>')?;
+            return Some(&xml[start..start + open.len() + end + 1]);
+        }
+        from = start + open.len();
+    }
+    None
+}
+
+/// 起始标签中某个属性的值
+pub(crate) fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let key = format!("{}=\"", name);
+    let mut from = 0;
+    while let Some(pos) = tag[from..].find(&key) {
+        let start = from + pos;
+        // 前一个字符须是空白，避免匹配到以该名称结尾的其他属性
+        if tag[..start].ends_with(char::is_whitespace) {
+            let value = &tag[start + key.len()..];
+            let end = value.find('"')?;
+            return Some(unescape_xml(&value[..end]));
+        }
+        from = start + key.len();
+    }
+    None
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub(crate) fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
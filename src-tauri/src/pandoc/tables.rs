@@ -0,0 +1,499 @@
+use calamine::{open_workbook_auto, Data, Reader};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+use super::attrs::Attributes;
+
+/// `!include <path> {attrs}` 指令，路径含空格时可加引号
+pub(crate) const INCLUDE_PATTERN: &str =
+    r#"^!include\s+(?:"(?P<quoted>[^"]+)"|(?P<path>\S+))\s*(?P<attrs>\{.*\})?\s*$"#;
+
+/// 展开 Markdown 中的表格引用，生成 pandoc 可识别的 pipe/grid 表格。
+///
+/// 支持两种写法：
+/// - 围栏代码块：```` ```csv ```` 或 ```` ```{.csv caption="标题" #tbl:data} ````，
+///   内容为内联 CSV；也可通过 `file="data.csv"` 引用外部文件
+/// - 单行指令：`!include data.xlsx {sheet="Sheet1" range="A1:D20" caption="标题" #tbl:data}`
+///
+/// 无法读取的引用会记录日志并保持原样。
+pub fn expand_table_includes(content: &str, base_dir: Option<&Path>) -> Result<String, String> {
+    let include_regex =
+        Regex::new(INCLUDE_PATTERN).map_err(|e| format!("Failed to compile regex: {}", e))?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some((fence_char, fence_len, info)) = parse_fence_open(line) {
+            let close_idx = find_fence_close(&lines, i + 1, fence_char, fence_len);
+            let end = close_idx.unwrap_or(lines.len());
+            let attrs = Attributes::parse(&info.replace(['{', '}'], " "));
+
+            if is_table_fence(&attrs) {
+                let body = lines[i + 1..end].join("\n");
+                match render_fenced_table(&body, &attrs, base_dir) {
+                    Ok(table) => output.push(table),
+                    Err(err) => {
+                        log::error!("Failed to expand table block: {}", err);
+                        output.extend(
+                            lines[i..(end + 1).min(lines.len())]
+                                .iter()
+                                .map(|l| l.to_string()),
+                        );
+                    }
+                }
+            } else {
+                // 其他代码块原样保留，不在其中展开指令
+                output.extend(
+                    lines[i..(end + 1).min(lines.len())]
+                        .iter()
+                        .map(|l| l.to_string()),
+                );
+            }
+            i = end + 1;
+            continue;
+        }
+
+        if let Some(caps) = include_regex.captures(line.trim_end()) {
+            let path = caps
+                .name("quoted")
+                .or_else(|| caps.name("path"))
+                .map(|m| m.as_str())
+                .unwrap_or("");
+            if is_table_file(path) {
+                let attrs = caps
+                    .name("attrs")
+                    .map(|m| Attributes::parse(m.as_str()))
+                    .unwrap_or_default();
+                match render_file_table(path, &attrs, base_dir) {
+                    Ok(table) => output.push(table),
+                    Err(err) => {
+                        log::error!("Failed to include table {}: {}", path, err);
+                        output.push(line.to_string());
+                    }
+                }
+                i += 1;
+                continue;
+            }
+        }
+
+        output.push(line.to_string());
+        i += 1;
+    }
+
+    let mut result = output.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// 解析围栏代码块起始行，返回 (围栏字符, 长度, info string)
+pub(crate) fn parse_fence_open(line: &str) -> Option<(char, usize, String)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let fence_char = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence_len = rest.chars().take_while(|c| *c == fence_char).count();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = rest[fence_len..].trim().to_string();
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence_char, fence_len, info))
+}
+
+pub(crate) fn find_fence_close(
+    lines: &[&str],
+    start: usize,
+    fence_char: char,
+    fence_len: usize,
+) -> Option<usize> {
+    (start..lines.len()).find(|&idx| {
+        let trimmed = lines[idx].trim();
+        trimmed.len() >= fence_len && trimmed.chars().all(|c| c == fence_char)
+    })
+}
+
+fn is_table_fence(attrs: &Attributes) -> bool {
+    attrs.has_class("csv") || attrs.has_class("tsv") || attrs.has_class("table")
+}
+
+fn is_table_file(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    [".csv", ".tsv", ".xlsx", ".xlsm", ".xls", ".ods"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+}
+
+fn render_fenced_table(
+    body: &str,
+    attrs: &Attributes,
+    base_dir: Option<&Path>,
+) -> Result<String, String> {
+    if let Some(file) = attrs.get("file") {
+        return render_file_table(file, attrs, base_dir);
+    }
+
+    let delimiter = delimiter_for(attrs, attrs.has_class("tsv"));
+    let rows = read_delimited(body.as_bytes(), delimiter)?;
+    render_table(rows, attrs)
+}
+
+fn render_file_table(
+    path: &str,
+    attrs: &Attributes,
+    base_dir: Option<&Path>,
+) -> Result<String, String> {
+    let resolved = resolve_table_path(path, base_dir)
+        .ok_or_else(|| format!("Table file not found: {}", path))?;
+    let lower = path.to_lowercase();
+
+    let rows = if lower.ends_with(".csv") || lower.ends_with(".tsv") {
+        let bytes =
+            std::fs::read(&resolved).map_err(|e| format!("Failed to read table file: {}", e))?;
+        read_delimited(&bytes, delimiter_for(attrs, lower.ends_with(".tsv")))?
+    } else {
+        read_spreadsheet(&resolved, attrs.get("sheet"), attrs.get("range"))?
+    };
+
+    render_table(rows, attrs)
+}
+
+fn resolve_table_path(path: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
+    let candidate = Path::new(path);
+    if candidate.is_absolute() {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+    base_dir
+        .map(|base| base.join(candidate))
+        .filter(|joined| joined.is_file())
+}
+
+fn delimiter_for(attrs: &Attributes, is_tsv: bool) -> u8 {
+    match attrs.get("delimiter") {
+        Some("\\t") | Some("tab") => b'\t',
+        Some(d) if d.len() == 1 => d.as_bytes()[0],
+        _ if is_tsv => b'\t',
+        _ => b',',
+    }
+}
+
+fn read_delimited(bytes: &[u8], delimiter: u8) -> Result<Vec<Vec<String>>, String> {
+    // 去掉 Excel 导出 CSV 常见的 UTF-8 BOM
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(bytes);
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to parse CSV: {}", e))?;
+        rows.push(record.iter().map(|c| c.to_string()).collect());
+    }
+    Ok(rows)
+}
+
+fn read_spreadsheet(
+    path: &Path,
+    sheet: Option<&str>,
+    range: Option<&str>,
+) -> Result<Vec<Vec<String>>, String> {
+    let mut workbook =
+        open_workbook_auto(path).map_err(|e| format!("Failed to open spreadsheet: {}", e))?;
+
+    let sheet_names = workbook.sheet_names();
+    let sheet_name = match sheet {
+        Some(name) if sheet_names.iter().any(|s| s == name) => name.to_string(),
+        // 也允许用从 1 开始的序号指定工作表
+        Some(index) => index
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|n| sheet_names.get(n).cloned())
+            .ok_or_else(|| format!("Sheet '{}' not found", index))?,
+        None => sheet_names
+            .first()
+            .cloned()
+            .ok_or_else(|| "Spreadsheet has no sheets".to_string())?,
+    };
+
+    let mut data = workbook
+        .worksheet_range(&sheet_name)
+        .map_err(|e| format!("Failed to read sheet '{}': {}", sheet_name, e))?;
+
+    if let Some(range) = range {
+        let requested =
+            parse_cell_range(range).ok_or_else(|| format!("Invalid cell range: {}", range))?;
+        // calamine 按区域大小分配单元格，先限制在工作表已有数据的范围内，
+        // `A1:XFD1048576` 这样的整表区域不会占用大量内存
+        let (start, end) = data
+            .start()
+            .zip(data.end())
+            .and_then(|(first, last)| clamp_range(requested, first, last))
+            .ok_or_else(|| format!("Cell range {} contains no data", range))?;
+        data = data.range(start, end);
+    }
+
+    Ok(data
+        .rows()
+        .map(|row| row.iter().map(format_cell).collect())
+        .collect())
+}
+
+fn format_cell(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(value) if value.time() == chrono::NaiveTime::MIN => {
+                value.format("%Y-%m-%d").to_string()
+            }
+            Some(value) => value.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => cell.to_string(),
+        },
+        Data::Error(err) => format!("#{:?}", err),
+        other => other.to_string(),
+    }
+}
+
+/// 解析 `A1:D20` 形式的区域，返回从 0 开始的 (行, 列) 坐标
+fn parse_cell_range(range: &str) -> Option<((u32, u32), (u32, u32))> {
+    let (start, end) = range.split_once(':').unwrap_or((range, range));
+    let start = parse_cell_ref(start.trim())?;
+    let end = parse_cell_ref(end.trim())?;
+    Some((
+        (start.0.min(end.0), start.1.min(end.1)),
+        (start.0.max(end.0), start.1.max(end.1)),
+    ))
+}
+
+/// 取区域与数据范围 `first..=last` 的交集，没有交集时返回 None
+fn clamp_range(
+    (start, end): ((u32, u32), (u32, u32)),
+    first: (u32, u32),
+    last: (u32, u32),
+) -> Option<((u32, u32), (u32, u32))> {
+    let start = (start.0.max(first.0), start.1.max(first.1));
+    let end = (end.0.min(last.0), end.1.min(last.1));
+    (start.0 <= end.0 && start.1 <= end.1).then_some((start, end))
+}
+
+fn parse_cell_ref(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.replace('$', "");
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() {
+        return None;
+    }
+
+    let mut col: u32 = 0;
+    for ch in letters.chars() {
+        if !ch.is_ascii_alphabetic() {
+            return None;
+        }
+        // 过长的列号溢出时视为无效引用
+        col = col
+            .checked_mul(26)?
+            .checked_add(ch.to_ascii_uppercase() as u32 - 'A' as u32 + 1)?;
+    }
+    let row: u32 = digits.parse().ok()?;
+    if row == 0 {
+        return None;
+    }
+    Some((row - 1, col - 1))
+}
+
+pub(crate) fn render_table(
+    mut rows: Vec<Vec<String>>,
+    attrs: &Attributes,
+) -> Result<String, String> {
+    rows.retain(|row| row.iter().any(|c| !c.trim().is_empty()));
+    if rows.is_empty() {
+        return Err("Table is empty".to_string());
+    }
+
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    for row in rows.iter_mut() {
+        row.resize(columns, String::new());
+        for cell in row.iter_mut() {
+            *cell = cell.trim().replace('|', "\\|");
+        }
+    }
+
+    let has_header = attrs.flag("header").unwrap_or(true);
+    let multiline = rows.iter().flatten().any(|c| c.contains('\n'));
+    let use_grid = match attrs.get("format") {
+        Some("grid") => true,
+        Some("pipe") => false,
+        _ => multiline || !has_header,
+    };
+
+    let mut table = if use_grid {
+        render_grid_table(&rows, has_header)
+    } else {
+        render_pipe_table(&rows)
+    };
+
+    let caption = attrs.get("caption").unwrap_or("").trim();
+    match (&attrs.id, caption.is_empty()) {
+        (Some(id), _) => table.push_str(&format!("\n\n: {} {{#{}}}", caption, id)),
+        (None, false) => table.push_str(&format!("\n\n: {}", caption)),
+        (None, true) => {}
+    }
+
+    Ok(table)
+}
+
+/// 解析 HTML 中的第一个 `<table>`（如 pandas DataFrame 的输出），返回各行单元格文本
+pub(crate) fn html_table_rows(html: &str) -> Result<Vec<Vec<String>>, String> {
+    let compile =
+        |pattern: &str| Regex::new(pattern).map_err(|e| format!("Failed to compile regex: {}", e));
+    let table_regex = compile(r"(?is)<table[^>]*>(.*?)</table>")?;
+    let row_regex = compile(r"(?is)<tr[^>]*>(.*?)</tr>")?;
+    let cell_regex = compile(r"(?is)<t[hd][^>]*>(.*?)</t[hd]>")?;
+    let tag_regex = compile(r"(?s)<[^>]*>")?;
+
+    let Some(table) = table_regex.captures(html).and_then(|c| c.get(1)) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(row_regex
+        .captures_iter(table.as_str())
+        .map(|row| {
+            cell_regex
+                .captures_iter(&row[1])
+                .map(|cell| decode_html_entities(&tag_regex.replace_all(&cell[1], "")))
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| !cells.is_empty())
+        .collect())
+}
+
+fn decode_html_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn render_pipe_table(rows: &[Vec<String>]) -> String {
+    let flatten = |cell: &String| cell.replace('\n', " ");
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+
+    lines.push(format!(
+        "| {} |",
+        rows[0].iter().map(flatten).collect::<Vec<_>>().join(" | ")
+    ));
+    lines.push(format!("|{}|", vec!["---"; rows[0].len()].join("|")));
+    for row in &rows[1..] {
+        lines.push(format!(
+            "| {} |",
+            row.iter().map(flatten).collect::<Vec<_>>().join(" | ")
+        ));
+    }
+    lines.join("\n")
+}
+
+fn render_grid_table(rows: &[Vec<String>], has_header: bool) -> String {
+    let columns = rows[0].len();
+    let mut widths = vec![3usize; columns];
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            for part in cell.lines() {
+                widths[idx] = widths[idx].max(UnicodeWidthStr::width(part));
+            }
+        }
+    }
+
+    let separator = |fill: char| {
+        let parts: Vec<String> = widths
+            .iter()
+            .map(|w| fill.to_string().repeat(w + 2))
+            .collect();
+        format!("+{}+", parts.join("+"))
+    };
+
+    let mut lines = vec![separator('-')];
+    for (row_idx, row) in rows.iter().enumerate() {
+        let cell_lines: Vec<Vec<&str>> = row.iter().map(|c| c.lines().collect()).collect();
+        let height = cell_lines.iter().map(|l| l.len()).max().unwrap_or(1).max(1);
+
+        for line_idx in 0..height {
+            let parts: Vec<String> = cell_lines
+                .iter()
+                .enumerate()
+                .map(|(col, parts)| {
+                    let text = parts.get(line_idx).copied().unwrap_or("");
+                    let pad = widths[col] - UnicodeWidthStr::width(text);
+                    format!(" {}{} ", text, " ".repeat(pad))
+                })
+                .collect();
+            lines.push(format!("|{}|", parts.join("|")));
+        }
+
+        let fill = if has_header && row_idx == 0 { '=' } else { '-' };
+        lines.push(separator(fill));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_references() {
+        assert_eq!(parse_cell_ref("A1"), Some((0, 0)));
+        assert_eq!(parse_cell_ref("$AA$10"), Some((9, 26)));
+        assert_eq!(parse_cell_ref("XFD1048576"), Some((1_048_575, 16_383)));
+        assert_eq!(parse_cell_ref("A0"), None);
+        assert_eq!(parse_cell_ref("12"), None);
+        assert_eq!(
+            parse_cell_range("D20:B2"),
+            Some(((1, 1), (19, 3))),
+            "corners are normalized"
+        );
+    }
+
+    #[test]
+    fn overflowing_cell_references_are_invalid() {
+        assert_eq!(parse_cell_ref("ZZZZZZZZZZ1"), None);
+        assert_eq!(parse_cell_ref("A99999999999"), None);
+        assert_eq!(parse_cell_range("A1:ZZZZZZZZZZ1"), None);
+    }
+
+    #[test]
+    fn ranges_are_clamped_to_the_data() {
+        let whole_sheet = parse_cell_range("A1:XFD1048576").unwrap();
+        assert_eq!(
+            clamp_range(whole_sheet, (0, 0), (9, 3)),
+            Some(((0, 0), (9, 3)))
+        );
+        assert_eq!(
+            clamp_range(((2, 1), (5, 2)), (0, 0), (9, 3)),
+            Some(((2, 1), (5, 2)))
+        );
+        assert_eq!(
+            clamp_range(((0, 0), (20, 20)), (3, 2), (9, 3)),
+            Some(((3, 2), (9, 3)))
+        );
+        assert_eq!(clamp_range(((20, 0), (30, 3)), (0, 0), (9, 3)), None);
+        assert_eq!(clamp_range(((0, 5), (9, 8)), (0, 0), (9, 3)), None);
+    }
+}
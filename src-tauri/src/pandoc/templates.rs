@@ -1,74 +1,224 @@
-use aes::Aes256;
-use cbc::Decryptor;
-use cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Manager};
 
+use super::crypto::decrypt_template;
+use super::generator::{build_reference_doc, StyleSpec};
+use super::library::{user_template_path, user_templates};
+use super::license::{template_key, verified_license};
+use super::overrides::{apply_style_overrides, StyleOverrides};
+use super::packs::{find_pack, list_packs, pack_reference_doc};
+use super::runtime::{rewrite_staged, write_staged, StagedFile};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct TemplateInfo {
-    pub reference_doc: String,
+    /// 可直接使用的参考文档；受保护或按样式描述生成的模板为 None，
+    /// 转换时传入 `template_id`，由转换器准备并在结束时删除
+    pub reference_doc: Option<String>,
     pub protected_path: String,
+    /// 模板包附带的 Lua 过滤器，转换时按顺序运行
+    pub lua_filters: Vec<String>,
+    /// 模板包附带的引文样式（CSL）
+    pub csl: Option<String>,
 }
 
 #[derive(Debug, Clone)]
-struct TemplateResource {
-    path: PathBuf,
-    encrypted: bool,
+enum TemplateResource {
+    /// 参考文档文件，受保护模板按授权解密
+    File {
+        id: String,
+        path: PathBuf,
+        encrypted: bool,
+    },
+    /// 清单中只有样式描述、没有 .docx 的内置模板，按描述生成
+    Spec(Box<StyleSpec>),
 }
 
+/// 查找模板并给出转换所需的信息；不解密、不写入 runtime 文件
+///
+/// 受保护模板只检查当前授权能否使用，解密留到 `stage_template`，由转换过程持有其 guard。
 pub fn prepare_template(
     app_handle: &AppHandle,
     template_name: &str,
-    encrypted: bool,
-    key_string: String,
 ) -> Result<TemplateInfo, String> {
     // Try to find template in resources
-    let resource = find_template_resource(app_handle, template_name, encrypted)?;
-
-    let cache_root = app_handle
-        .path()
-        .cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?;
-
-    let runtime_dir = cache_root
-        .join("formatsman")
-        .join("templates")
-        .join("runtime");
-    fs::create_dir_all(&runtime_dir).map_err(|e| format!("Failed to create runtime dir: {}", e))?;
-
-    let millis = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| format!("Time error: {}", e))?
-        .as_millis();
-
-    let runtime_docx = runtime_dir.join(format!("{}-{}.docx", template_name, millis));
-    if resource.encrypted {
-        let encrypted_bytes = fs::read(&resource.path)
-            .map_err(|e| format!("Failed to read protected template: {}", e))?;
-        let decrypted = decrypt_bytes_aes_cbc(&encrypted_bytes, key_string)?;
-        fs::write(&runtime_docx, decrypted)
-            .map_err(|e| format!("Failed to stage protected template: {}", e))?;
-    } else {
-        fs::copy(&resource.path, &runtime_docx)
-            .map_err(|e| format!("Failed to stage template: {}", e))?;
+    let resource = find_template_resource(app_handle, template_name)?;
+
+    let (reference_doc, protected_path) = match &resource {
+        TemplateResource::File {
+            id,
+            path,
+            encrypted: true,
+        } => {
+            template_key(app_handle, id)?;
+            (None, path.to_string_lossy().to_string())
+        }
+        TemplateResource::File { path, .. } => {
+            let path = path.to_string_lossy().to_string();
+            (Some(path.clone()), path)
+        }
+        TemplateResource::Spec(_) => (None, String::new()),
+    };
+    // 参考文档来自模板包时，包内的过滤器与引文样式一并交给转换
+    let pack = find_pack(app_handle, template_name)?.filter(|p| p.reference_doc == protected_path);
+
+    Ok(TemplateInfo {
+        reference_doc,
+        protected_path,
+        lua_filters: pack.as_ref().map(|p| p.filters.clone()).unwrap_or_default(),
+        csl: pack.and_then(|p| p.csl),
+    })
+}
+
+/// 为转换准备模板的参考文档，返回其路径与 runtime 文件的 guard
+///
+/// 未加密的模板直接使用原文件；受保护模板在内存中解密、样式描述在内存中生成，
+/// 再写入权限受限的 runtime 文件，guard 离开作用域时删除。
+pub fn stage_template(
+    app_handle: &AppHandle,
+    template_id: &str,
+) -> Result<(PathBuf, Option<StagedFile>), String> {
+    let resource = find_template_resource(app_handle, template_id)?;
+    if let TemplateResource::File {
+        path,
+        encrypted: false,
+        ..
+    } = resource
+    {
+        return Ok((path, None));
     }
+    let bytes = read_resource_bytes(app_handle, &resource)?;
+    let staged = write_staged(app_handle, template_id, &bytes)?;
+    Ok((staged.path().to_path_buf(), Some(staged)))
+}
+
+/// 按样式描述生成参考文档并放入 runtime 目录，与内置模板一样在转换后删除
+pub fn prepare_generated_template(
+    app_handle: &AppHandle,
+    spec: &StyleSpec,
+) -> Result<TemplateInfo, String> {
+    let bytes = build_reference_doc(spec)?;
+    let runtime_docx = write_staged(app_handle, "generated", &bytes)?.keep();
 
     Ok(TemplateInfo {
-        reference_doc: runtime_docx.to_string_lossy().to_string(),
-        protected_path: resource.path.to_string_lossy().to_string(),
+        reference_doc: Some(runtime_docx.to_string_lossy().to_string()),
+        protected_path: String::new(),
+        lua_filters: Vec::new(),
+        csl: None,
     })
 }
 
+/// 将样式覆盖写入参考文档的 runtime 副本，返回该副本的 guard
+///
+/// 参考文档本身已在 runtime 目录时直接改写；否则（内置或用户模板的原始文件）另存一份副本，原文件保持不变。
+pub fn stage_style_overrides(
+    app_handle: &AppHandle,
+    reference_doc: &Path,
+    overrides: &StyleOverrides,
+) -> Result<StagedFile, String> {
+    let bytes =
+        fs::read(reference_doc).map_err(|e| format!("Failed to read reference doc: {}", e))?;
+    let patched = apply_style_overrides(&bytes, overrides)?;
+
+    if let Some(staged) = StagedFile::adopt(app_handle, reference_doc) {
+        rewrite_staged(&staged, &patched)?;
+        return Ok(staged);
+    }
+    let stem = reference_doc
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "template".to_string());
+    write_staged(app_handle, &format!("{}-override", stem), &patched)
+}
+
+/// 读取模板内容，受保护模板只在内存中解密，不写入磁盘
+pub fn read_template_bytes(app_handle: &AppHandle, template_name: &str) -> Result<Vec<u8>, String> {
+    let resource = find_template_resource(app_handle, template_name)?;
+    read_resource_bytes(app_handle, &resource)
+}
+
+/// 模板在磁盘上的原始内容及是否受保护，不解密
+///
+/// 受保护模板为密文，按样式描述生成的模板为描述的 JSON，用于判断模板是否有变化。
+pub fn read_stored_template(
+    app_handle: &AppHandle,
+    template_name: &str,
+) -> Result<(Vec<u8>, bool), String> {
+    match find_template_resource(app_handle, template_name)? {
+        TemplateResource::File {
+            path, encrypted, ..
+        } => {
+            let bytes = fs::read(&path).map_err(|e| format!("Failed to read template: {}", e))?;
+            Ok((bytes, encrypted))
+        }
+        TemplateResource::Spec(spec) => serde_json::to_vec(&spec)
+            .map(|bytes| (bytes, false))
+            .map_err(|e| format!("Failed to serialize style spec: {}", e)),
+    }
+}
+
+fn read_resource_bytes(
+    app_handle: &AppHandle,
+    resource: &TemplateResource,
+) -> Result<Vec<u8>, String> {
+    match resource {
+        TemplateResource::File {
+            id,
+            path,
+            encrypted: true,
+        } => {
+            let key_string = template_key(app_handle, id)?;
+            let encrypted_bytes =
+                fs::read(path).map_err(|e| format!("Failed to read protected template: {}", e))?;
+            decrypt_template(&encrypted_bytes, &key_string)
+        }
+        TemplateResource::File { path, .. } => {
+            fs::read(path).map_err(|e| format!("Failed to read template: {}", e))
+        }
+        TemplateResource::Spec(spec) => build_reference_doc(spec),
+    }
+}
+
 fn find_template_resource(
     app_handle: &AppHandle,
     template_id: &str,
-    encrypted: bool,
 ) -> Result<TemplateResource, String> {
+    let resource = |path: PathBuf, encrypted: bool| TemplateResource::File {
+        id: template_id.to_string(),
+        path,
+        encrypted,
+    };
+
+    // 用户导入的模板不加密
+    if let Some(path) = user_template_path(app_handle, template_id)? {
+        return Ok(resource(path, false));
+    }
+
+    if let Some((path, encrypted)) = pack_reference_doc(app_handle, template_id)? {
+        return Ok(resource(path, encrypted));
+    }
+
+    match find_bundled_template(app_handle, template_id) {
+        Some(path) => {
+            let encrypted = is_bundled_protected(app_handle, template_id, &path)?;
+            Ok(resource(path, encrypted))
+        }
+        None => match bundled_template_meta(app_handle, template_id)?.and_then(|m| m.styleSpec) {
+            Some(spec) => Ok(TemplateResource::Spec(Box::new(spec))),
+            None => Err(format!(
+                "Template '{}' not found in resources/templates, user templates or template packs",
+                template_id
+            )),
+        },
+    }
+}
+
+/// 内置模板（resources/templates）的路径
+pub(crate) fn find_bundled_template(app_handle: &AppHandle, template_id: &str) -> Option<PathBuf> {
     let filename = template_id.to_string();
 
     // 1. Development Environment: Check project root relative paths
@@ -80,10 +230,7 @@ fn find_template_resource(
 
     for path in &dev_candidates {
         if path.exists() {
-            return Ok(TemplateResource {
-                path: path.clone(),
-                encrypted,
-            });
+            return Some(path.clone());
         }
     }
 
@@ -100,23 +247,51 @@ fn find_template_resource(
             .resolve(candidate, BaseDirectory::Resource)
         {
             if path.exists() {
-                return Ok(TemplateResource { path, encrypted });
+                return Some(path);
             }
         }
     }
 
-    Err(format!(
-        "Template '{}' not found in resources/templates",
-        template_id
-    ))
+    None
 }
 
+/// 内置模板清单，与模板文件同在 resources/templates
+const BUNDLED_MANIFEST: &str = "templates.json";
+
 #[derive(Debug, Clone, Serialize)]
 pub struct TemplateListResponse {
-    pub templates: Vec<TemplateMeta>,
+    pub templates: Vec<TemplateEntry>,
     pub has_premium: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    Bundled,
+    User,
+    Pack,
+}
+
+/// 模板目录中的一项：清单中的元数据加上当前可用状态
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateEntry {
+    #[serde(flatten)]
+    pub meta: TemplateMeta,
+    pub source: TemplateSource,
+    /// 参考文档存在，或可按样式描述生成
+    pub available: bool,
+    /// 参考文档受保护（加密）
+    pub protected: bool,
+    /// 当前授权是否包含受保护模板及其 key（不实际解密）；模板不受保护时为 None
+    pub decryptable: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BundledManifest {
+    #[serde(default)]
+    templates: Vec<TemplateMeta>,
+}
+
 #[allow(non_snake_case)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateMeta {
@@ -129,32 +304,115 @@ pub struct TemplateMeta {
     pub member: bool,
     #[serde(default)]
     pub defaultPreset: serde_json::Value,
+    /// class → Word 样式映射，见 stylemap
+    #[serde(default)]
+    pub customStyles: BTreeMap<String, String>,
+    /// 用样式描述代替手工制作的参考文档
+    #[serde(default)]
+    pub styleSpec: Option<StyleSpec>,
 }
 
-pub fn list_templates(_app_handle: &AppHandle) -> Result<TemplateListResponse, String> {
-    // DEPRECATED: Metadata is now handled by the frontend importing templates.ts directly.
-    // This function returns empty list to avoid breaking if called,
-    // but should be avoided in favor of frontend config.
+/// 汇总内置模板、用户模板与已安装的模板包，前端与命令行共用同一份目录
+///
+/// 内置模板的 member 标记即是否加密，与 `prepare_template` 的约定一致。
+pub fn list_templates(app_handle: &AppHandle) -> Result<TemplateListResponse, String> {
+    let mut templates = Vec::new();
+    // 只校验一次授权签名，按授权范围判断能否使用，不逐个解密模板
+    let license = verified_license(app_handle);
+    let now = Utc::now();
+    let unlocks = |template_id: &str| {
+        license
+            .as_ref()
+            .is_some_and(|license| license.unlocks(template_id, now))
+    };
+
+    for meta in bundled_templates(app_handle)? {
+        let path = find_bundled_template(app_handle, &meta.id);
+        let protected = meta.member;
+        let decryptable = match &path {
+            Some(_) if protected => Some(unlocks(&meta.id)),
+            _ => None,
+        };
+        templates.push(TemplateEntry {
+            available: path.is_some() || meta.styleSpec.is_some(),
+            protected,
+            decryptable,
+            source: TemplateSource::Bundled,
+            meta,
+        });
+    }
+
+    for template in user_templates(app_handle)? {
+        templates.push(TemplateEntry {
+            meta: TemplateMeta {
+                id: template.id,
+                name: template.name,
+                description: template.description,
+                category: "user".to_string(),
+                member: false,
+                defaultPreset: serde_json::Value::Null,
+                customStyles: BTreeMap::new(),
+                styleSpec: None,
+            },
+            source: TemplateSource::User,
+            available: true,
+            protected: false,
+            decryptable: None,
+        });
+    }
+
+    for pack in list_packs(app_handle)? {
+        let path = PathBuf::from(&pack.reference_doc);
+        let protected = pack.manifest.files.encrypted;
+        let decryptable = (protected && path.exists()).then(|| unlocks(&pack.manifest.meta.id));
+        templates.push(TemplateEntry {
+            meta: pack.manifest.meta,
+            source: TemplateSource::Pack,
+            available: path.exists(),
+            protected,
+            decryptable,
+        });
+    }
+
+    let has_premium = templates.iter().any(|t| t.meta.member);
     Ok(TemplateListResponse {
-        templates: vec![],
-        has_premium: false,
+        templates,
+        has_premium,
     })
 }
 
-fn decrypt_bytes_aes_cbc(data: &[u8], key_string: String) -> Result<Vec<u8>, String> {
-    if data.len() < 16 {
-        return Err("Encrypted content is too short".to_string());
-    }
-
-    let mut hasher = Sha256::new();
-    hasher.update(key_string.as_bytes());
-    let key_bytes = hasher.finalize();
+/// 读取内置模板清单；清单缺失时按无内置模板处理
+fn bundled_templates(app_handle: &AppHandle) -> Result<Vec<TemplateMeta>, String> {
+    let Some(path) = find_bundled_template(app_handle, BUNDLED_MANIFEST) else {
+        log::warn!("Bundled template manifest not found");
+        return Ok(Vec::new());
+    };
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read template manifest: {}", e))?;
+    let manifest: BundledManifest =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid template manifest: {}", e))?;
+    Ok(manifest.templates)
+}
 
-    let (iv, ciphertext) = data.split_at(16);
-    let mut buffer = ciphertext.to_vec();
-    let decrypted = Decryptor::<Aes256>::new((&key_bytes).into(), iv.into())
-        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
-        .map_err(|_| "Failed to decrypt protected content".to_string())?;
+/// 内置模板清单中的一项
+pub(crate) fn bundled_template_meta(
+    app_handle: &AppHandle,
+    template_id: &str,
+) -> Result<Option<TemplateMeta>, String> {
+    Ok(bundled_templates(app_handle)?
+        .into_iter()
+        .find(|t| t.id == template_id))
+}
 
-    Ok(decrypted.to_vec())
+/// 内置模板是否受保护：以清单的 member 标记为准，未列入清单的按文件内容判断
+fn is_bundled_protected(
+    app_handle: &AppHandle,
+    template_id: &str,
+    path: &Path,
+) -> Result<bool, String> {
+    if let Some(meta) = bundled_template_meta(app_handle, template_id)? {
+        return Ok(meta.member);
+    }
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read template: {}", e))?;
+    Ok(!bytes.starts_with(b"PK\x03\x04"))
 }
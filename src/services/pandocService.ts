@@ -9,11 +9,28 @@ export interface ConvertOptions {
   source_dir?: string
   source_name?: string
   reference_doc?: string
+  /** 未给出 reference_doc 时由后端按模板 ID 准备参考文档 */
+  template_id?: string
   metadata?: Record<string, any>  // Pandoc 元数据对象
   metadata_file?: string
   use_crossref: boolean
+  bibliography?: string[]
+  chinese_conversion?: ChineseConversion
+  /** front matter 与预设元数据冲突时的优先级，默认 preset */
+  metadata_precedence?: 'preset' | 'document'
+  /** 模板定义的 class → Word 样式映射 */
+  custom_styles?: Record<string, string>
+  /** 本次转换的样式覆盖，只作用于模板的 runtime 副本 */
+  style_overrides?: StyleOverrides
+  /** 模板包提供的 Lua 过滤器，按顺序运行 */
+  lua_filters?: string[]
+  /** 引文样式（CSL）路径 */
+  csl?: string
 }
 
+/** 简繁转换方向 */
+export type ChineseConversion = 's2t' | 's2tw' | 's2hk' | 't2s'
+
 export type InputSourceType = 'file' | 'text'
 
 export interface PrepareInputPayload {
@@ -21,23 +38,40 @@ export interface PrepareInputPayload {
   path?: string
   original_name?: string
   selected_markdown?: string
+  markdown_order?: string[]
   content?: string
   suggested_name?: string
 }
 
+export interface InputOptions {
+  include_notebook_code?: boolean
+  html_main_content?: boolean
+  normalize_chat?: boolean
+  cjk_emphasis?: boolean
+  cjk_punctuation?: boolean
+  cjk_spacing?: boolean
+}
+
 export interface PreparedInput {
   markdown_path: string
   assets_dir: string
   image_count: number
   copied_images: string[]
   markdown_files: string[]
+  assembled_files: string[]
   source_name?: string
   source_dir?: string
+  bibliography: string[]
 }
 
 export interface TemplateInfo {
-  reference_doc: string
+  /** 受保护或按样式描述生成的模板为 null，转换时改传 template_id */
+  reference_doc: string | null
   protected_path: string
+  /** 模板包附带的 Lua 过滤器 */
+  lua_filters: string[]
+  /** 模板包附带的引文样式（CSL）路径 */
+  csl: string | null
 }
 
 import type { TemplateConfig } from '../types/templateConfig'
@@ -49,10 +83,213 @@ export interface TemplateMeta {
   category: string
   member: boolean
   defaultPreset?: Partial<TemplateConfig>
+  /** class → Word 样式映射，例如 { lead: 'Lead Paragraph' } */
+  customStyles?: Record<string, string>
+  /** 用样式描述代替手工制作的参考文档 */
+  styleSpec?: StyleSpec
+}
+
+export interface CustomStyleIssue {
+  class: string
+  style: string
+  message: string
+}
+
+export type StyleKind = 'paragraph' | 'character' | 'table' | 'numbering'
+
+export interface RunFonts {
+  ascii?: string
+  east_asia?: string
+  h_ansi?: string
+  cs?: string
+}
+
+export interface DocxStyle {
+  id: string
+  name: string
+  kind: StyleKind
+  based_on: string | null
+  custom: boolean
+  fonts?: RunFonts
+  size_pt: number | null
+}
+
+/** 模板检查结果，尺寸单位为毫米 */
+export interface TemplateReport {
+  paragraph_styles: DocxStyle[]
+  character_styles: DocxStyle[]
+  table_styles: DocxStyle[]
+  numbering_styles: DocxStyle[]
+  fonts: {
+    default_fonts: RunFonts
+    default_size_pt: number | null
+    theme: {
+      major_latin: string | null
+      major_east_asia: string | null
+      minor_latin: string | null
+      minor_east_asia: string | null
+    }
+    declared: string[]
+    used: string[]
+  }
+  page: {
+    width_mm: number
+    height_mm: number
+    landscape: boolean
+    paper: string | null
+    margins: Record<'top' | 'bottom' | 'left' | 'right' | 'header' | 'footer' | 'gutter', number>
+  } | null
+  pandoc_styles: { name: string; kind: StyleKind; present: boolean }[]
+  missing_pandoc_styles: string[]
+}
+
+/** 磅值或中文字号，例如 12、'小四' */
+export type FontSize = number | string
+
+export interface TextSpec {
+  /** 中文字体 */
+  font?: string
+  /** 西文字体 */
+  latin_font?: string
+  size?: FontSize
+  bold?: boolean
+  italic?: boolean
+  color?: string
+  align?: 'left' | 'center' | 'right' | 'justify'
+  /** 数字为倍数，'20pt' 为固定值 */
+  line_spacing?: number | string
+  /** 首行缩进、左缩进（字符数） */
+  first_line_indent?: number
+  left_indent?: number
+  /** 段前、段后（磅） */
+  space_before?: number
+  space_after?: number
+}
+
+/** 声明式模板样式描述，长度单位为毫米 */
+export interface StyleSpec {
+  page?: {
+    size?: string
+    width_mm?: number
+    height_mm?: number
+    landscape?: boolean
+    margins?: Partial<Record<'top' | 'bottom' | 'left' | 'right' | 'header' | 'footer', number>>
+  }
+  body?: TextSpec
+  headings?: TextSpec[]
+  title?: TextSpec
+  caption?: TextSpec
+  table_caption?: TextSpec
+  image_caption?: TextSpec
+  code?: TextSpec
+  footnote?: TextSpec
+  table?: {
+    borders?: 'three-line' | 'grid' | 'none'
+    header_bold?: boolean
+    text?: TextSpec
+  }
+}
+
+/** 单次转换的样式覆盖，未给出的项保持模板原值 */
+export interface StyleOverrides {
+  /** docDefaults，未单独设置的样式都会继承 */
+  defaults?: TextSpec
+  /** 按样式名或 ID 覆盖（不区分大小写），例如 Normal、Heading 1 */
+  styles?: Record<string, TextSpec>
+  page?: {
+    size?: string
+    width_mm?: number
+    height_mm?: number
+    landscape?: boolean
+    margins?: Partial<Record<'top' | 'bottom' | 'left' | 'right' | 'header' | 'footer', number>>
+  }
+}
+
+/** 用户导入的模板，id 以 `user-` 开头，可直接传给 prepareTemplate */
+export interface UserTemplate {
+  id: string
+  name: string
+  description: string
+  source_name: string
+  imported_at: number
+}
+
+/** 模板包清单（manifest.json），路径相对于包根目录 */
+export interface PackManifest extends TemplateMeta {
+  version?: string
+  files: {
+    referenceDoc: string
+    encrypted?: boolean
+    preset?: string
+    filters?: string[]
+    csl?: string
+    sample?: string
+    preview?: string
+  }
+}
+
+export interface PackValidation {
+  manifest: PackManifest | null
+  /** 有错误的包不能安装 */
+  errors: string[]
+  warnings: string[]
+}
+
+/** 已安装的模板包，id 可直接传给 prepareTemplate，路径均为绝对路径 */
+export interface TemplatePack {
+  manifest: PackManifest
+  dir: string
+  reference_doc: string
+  filters: string[]
+  csl: string | null
+  sample: string | null
+  preview: string | null
+}
+
+/** 加密后的模板文件信息 */
+export interface ProtectedTemplate {
+  output_path: string
+  plain_size: number
+  encrypted_size: number
+  /** 明文 .docx 的 SHA-256 */
+  sha256: string
+}
+
+/** 模板目录中的一项：元数据加上当前可用状态 */
+export interface TemplateEntry extends TemplateMeta {
+  source: 'bundled' | 'user' | 'pack'
+  /** 参考文档存在 */
+  available: boolean
+  /** 参考文档受保护（加密） */
+  protected: boolean
+  /** 受保护模板能否按当前授权解密，模板不受保护时为 null */
+  decryptable: boolean | null
+}
+
+/** 已安装的会员授权，不含模板密钥 */
+export interface LicenseStatus {
+  id: string
+  licensee: string
+  /** RFC 3339 时间或 YYYY-MM-DD，null 表示永久 */
+  expires_at: string | null
+  expired: boolean
+  /** 授权的模板 ID，`*` 表示全部 */
+  templates: string[]
+}
+
+/** 模板预览：示例文档套用模板与预设后的结果，路径均为绝对路径 */
+export interface TemplatePreview {
+  template_id: string
+  /** 受保护模板不缓存 DOCX，为 null */
+  docx_path: string | null
+  /** 独立的 HTML 文件，图片内嵌，字体与字号按模板样式近似 */
+  html_path: string
+  /** 直接使用了已有的缓存 */
+  cached: boolean
 }
 
 export interface TemplateListResponse {
-  templates: TemplateMeta[]
+  templates: TemplateEntry[]
   has_premium: boolean
 }
 
@@ -156,37 +393,169 @@ export const pandocService = {
   /**
    * 预处理输入，提取 Markdown 及图片到临时目录
    */
-  async prepareInput(payload: PrepareInputPayload): Promise<PreparedInput> {
-    return await invoke<PreparedInput>('prepare_input_payload', { source: payload })
+  async prepareInput(payload: PrepareInputPayload, options?: InputOptions): Promise<PreparedInput> {
+    return await invoke<PreparedInput>('prepare_input_payload', { source: payload, options })
   },
 
   /**
    * 准备模板，返回可用的运行时路径
+   * 受保护模板由后端按已安装的授权判断能否使用
    */
-  async prepareTemplate(templateName: string, isMember: boolean): Promise<TemplateInfo> {
-    // Pass local encryption key if available, otherwise empty string (ok for free templates)
-    const key = import.meta.env.VITE_TEMPLATE_ENCRYPTION_KEY || "";
-    
+  async prepareTemplate(templateName: string): Promise<TemplateInfo> {
     // Tauri v2 maps snake_case to camelCase in command args; Rust expects `templateName`.
-    return await invoke<TemplateInfo>('prepare_template_protected', { 
-      templateName, 
-      isMember,
-      key 
+    return await invoke<TemplateInfo>('prepare_template_protected', { templateName })
+  },
+
+  /**
+   * 按样式描述生成参考文档，返回可用的运行时路径
+   */
+  async prepareTemplateFromSpec(spec: StyleSpec): Promise<TemplateInfo> {
+    return await invoke<TemplateInfo>('prepare_template_from_spec', { spec })
+  },
+
+  /**
+   * 把 JSON/YAML 样式描述文件生成为 .docx 参考文档
+   */
+  async generateReferenceDoc(specPath: string, outputPath: string): Promise<string> {
+    return await invoke<string>('generate_reference_doc', { specPath, outputPath })
+  },
+
+  /**
+   * 导入 .docx/.dotx 作为用户模板
+   */
+  async importUserTemplate(path: string, name?: string): Promise<UserTemplate> {
+    return await invoke<UserTemplate>('import_user_template', { path, name })
+  },
+
+  async renameUserTemplate(id: string, name: string): Promise<UserTemplate> {
+    return await invoke<UserTemplate>('rename_user_template', { id, name })
+  },
+
+  async deleteUserTemplate(id: string): Promise<void> {
+    await invoke('delete_user_template', { id })
+  },
+
+  async listUserTemplates(): Promise<UserTemplate[]> {
+    return await invoke<UserTemplate[]>('list_user_templates')
+  },
+
+  /**
+   * 用给定 key 加密 .docx 模板并校验能否还原，供模板作者发布受保护模板
+   */
+  async protectTemplateFile(inputPath: string, outputPath: string, key: string): Promise<ProtectedTemplate> {
+    return await invoke<ProtectedTemplate>('protect_template_file', { inputPath, outputPath, key })
+  },
+
+  /**
+   * 校验模板包（zip），不安装
+   */
+  async validateTemplatePack(path: string): Promise<PackValidation> {
+    return await invoke<PackValidation>('validate_template_pack', { path })
+  },
+
+  /**
+   * 安装模板包，同 ID 的旧版本会被替换
+   */
+  async installTemplatePack(path: string): Promise<TemplatePack> {
+    return await invoke<TemplatePack>('install_template_pack', { path })
+  },
+
+  async uninstallTemplatePack(id: string): Promise<void> {
+    await invoke('uninstall_template_pack', { id })
+  },
+
+  async listTemplatePacks(): Promise<TemplatePack[]> {
+    return await invoke<TemplatePack[]>('list_template_packs')
+  },
+
+  /**
+   * 检查内置模板的样式、字体与页面设置
+   */
+  async inspectTemplate(templateName: string): Promise<TemplateReport> {
+    return await invoke<TemplateReport>('inspect_template', { templateName })
+  },
+
+  /**
+   * 检查任意模板文件，受保护模板需提供 key
+   */
+  async inspectTemplateFile(path: string, key?: string): Promise<TemplateReport> {
+    return await invoke<TemplateReport>('inspect_template_file', { path, key })
+  },
+
+  /**
+   * 校验并安装会员授权文件，离线可用
+   */
+  async installLicense(path: string): Promise<LicenseStatus> {
+    return await invoke<LicenseStatus>('install_license', { path })
+  },
+
+  /**
+   * 当前授权，未安装时为 null
+   */
+  async getLicenseStatus(): Promise<LicenseStatus | null> {
+    return await invoke<LicenseStatus | null>('license_status')
+  },
+
+  async removeLicense(): Promise<void> {
+    await invoke('remove_license')
+  },
+
+  /**
+   * 用内置示例文档生成模板预览，按模板与预设缓存
+   * metadata 为预设对应的 Pandoc 元数据（与正式转换相同）；refresh 为 true 时忽略缓存
+   */
+  async generateTemplatePreview(
+    templateId: string,
+    metadata?: Record<string, any>,
+    options?: { useCrossref?: boolean, refresh?: boolean }
+  ): Promise<TemplatePreview> {
+    return await invoke<TemplatePreview>('generate_template_preview', {
+      templateId,
+      metadata,
+      useCrossref: options?.useCrossref,
+      refresh: options?.refresh
     })
   },
 
+  async clearTemplatePreviews(): Promise<void> {
+    await invoke('clear_template_previews')
+  },
+
+  /**
+   * 按参考文档校验 class → 样式映射，返回无法使用的项
+   */
+  async validateCustomStyles(referenceDoc: string, customStyles: Record<string, string>): Promise<CustomStyleIssue[]> {
+    return await invoke<CustomStyleIssue[]>('validate_custom_styles', { referenceDoc, customStyles })
+  },
+
   /**
-   * 获取模板列表元数据
+   * 按模板 ID 校验 class → 样式映射，受保护模板同样需要授权
+   */
+  async validateTemplateStyles(templateId: string, customStyles: Record<string, string>): Promise<CustomStyleIssue[]> {
+    return await invoke<CustomStyleIssue[]>('validate_template_styles', { templateId, customStyles })
+  },
+
+  /**
+   * 获取模板目录：内置模板、用户模板与已安装的模板包
    */
   async getTemplates(): Promise<TemplateListResponse> {
-    // Use imported configuration directly instead of calling backend
-    const config = templatesConfig as { version: number, templates: TemplateMeta[] };
-    const templates = config.templates || [];
-    const has_premium = templates.some(t => t.member);
-    
-    return Promise.resolve({
-      templates,
-      has_premium
-    })
+    try {
+      return await invoke<TemplateListResponse>('list_templates')
+    } catch (error) {
+      // 后端目录不可用时退回到随前端打包的内置模板元数据
+      console.warn('Failed to list templates from backend:', error)
+      const config = templatesConfig as { version: number, templates: TemplateMeta[] };
+      const templates: TemplateEntry[] = (config.templates || []).map(t => ({
+        ...t,
+        source: 'bundled' as const,
+        available: true,
+        protected: t.member,
+        decryptable: null
+      }));
+      return {
+        templates,
+        has_premium: templates.some(t => t.member)
+      }
+    }
   }
 }
@@ -14,7 +14,6 @@ interface ImportMetaEnv {
   readonly VITE_GET_MEMBERSHIP_GSJ_ENDPOINT?: string;
   readonly VITE_ACTIVATE_MEMBERSHIP_GSJ_ENDPOINT?: string;
   readonly VITE_GET_DASHSCOPE_TOKEN_GSJ_ENDPOINT?: string;
-  readonly VITE_TEMPLATE_ENCRYPTION_KEY?: string;
   readonly VITE_TEMP_API_ENCRYPTION_KEY?: string;
 }
 